@@ -4,6 +4,8 @@ use std::{
     path::Path,
 };
 
+use regex::Regex;
+
 use crate::error::{self, ErrExt};
 
 pub fn write_config(
@@ -26,6 +28,15 @@ pub fn write_config(
         make_prefix(&config.shell, &color_scheme.new_line, &padding),
         config.new_line_content
     );
+    // The optional git colors default to the git segment's own ones, color by
+    // color.
+    let make_git_prefix = |colors: &Option<crate::toml::PartialColors>| {
+        let git_colors = &color_scheme.git.colors;
+        match colors {
+            Some(colors) => make_prefix(&config.shell, &colors.or(git_colors), &padding),
+            None => make_prefix(&config.shell, git_colors, &padding),
+        }
+    };
 
     write!(
         &mut writer,
@@ -34,18 +45,44 @@ pub fn write_config(
         reset_style = config.shell.reset_escape(),
         current_dir_prefix = make_prefix(&config.shell, &color_scheme.current_dir, &padding),
         current_dir_suffix = padding.clone(),
-        git_prefix = make_prefix(&config.shell, &color_scheme.git, &padding),
+        git_prefix = make_prefix(&config.shell, &color_scheme.git.colors, &padding),
         git_suffix = padding.clone(),
+        git_stale_prefix = make_git_prefix(&color_scheme.git_stale),
+        git_protected_prefix = make_git_prefix(&color_scheme.git_protected),
+        git_clean_prefix = make_git_prefix(&color_scheme.git.clean),
+        git_dirty_prefix = make_git_prefix(&color_scheme.git.dirty),
+        git_conflict_prefix = make_git_prefix(&color_scheme.git.conflict),
         new_line = new_line,
+        git_detached = format!("{:?}", &config.git.detached),
         git_merge = make_optional_string(&config.git.merge),
         git_rebase = make_optional_string(&config.git.rebase),
         git_cherry_pick = make_optional_string(&config.git.cherry_pick),
         git_revert = make_optional_string(&config.git.revert),
+        git_am = make_optional_string(&config.git.am),
+        git_bisect = make_optional_string(&config.git.bisect),
+        git_sparse = make_optional_string(&config.git.sparse),
+        git_partial = make_optional_string(&config.git.partial),
+        git_initial = make_optional_string(&config.git.initial),
+        git_rebase_onto = make_optional_string(&config.git.rebase_onto),
+        git_protected_branches = make_glob_regexes(&config.git.protected_branches),
+        git_conflicted = make_optional_string(&config.git.conflicted),
         git_unstaged = make_optional_string(&config.git.unstaged),
         git_staged = make_optional_string(&config.git.staged),
         git_stash = make_optional_string(&config.git.stash),
+        git_branch_stash = make_optional_string(&config.git.branch_stash),
         git_untracked = make_optional_string(&config.git.untracked),
-        git_remote = format!("{:?}", &config.git.remote)
+        git_submodules = make_optional_string(&config.git.submodules),
+        git_superproject = make_optional_string(&config.git.superproject),
+        git_bare = make_optional_string(&config.git.bare),
+        git_inside_git_dir = make_optional_string(&config.git.inside_git_dir),
+        git_remote = format!("{:?}", &config.git.remote),
+        git_error = make_git_error(&config.git.error),
+        git_commit_age = make_git_commit_age(&config.git.commit_age),
+        git_branch = make_git_branch(&config.git.branch)?,
+        git_status_args = make_git_status_args(&config.git),
+        git_large_repo = make_git_large_repo(&config.git.large_repo),
+        jj = make_jj(&config.jj),
+        hg = make_hg(&config.hg)
     )?;
     writer.flush().err_into()
 }
@@ -58,6 +95,145 @@ fn make_prefix(shell: &crate::toml::Shell, colors: &crate::toml::Colors, padding
     )
 }
 
+fn make_git_error(error: &Option<crate::toml::GitError>) -> String {
+    let Some(error) = error else {
+        return "None".to_string();
+    };
+
+    format!(
+        "Some(GitError {{ dubious_ownership: {}, corrupt_index: {} }})",
+        make_optional_string(&error.dubious_ownership),
+        make_optional_string(&error.corrupt_index)
+    )
+}
+
+// Without a symbol nor a stale-after, reading the time of the last commit
+// would be wasted, so the prompt only reads it when the table is generated.
+fn make_git_commit_age(commit_age: &Option<crate::toml::GitCommitAge>) -> String {
+    match commit_age {
+        Some(crate::toml::GitCommitAge {
+            symbol: None,
+            stale_after: None,
+        })
+        | None => "None".to_string(),
+        _ => format!("{commit_age:?}"),
+    }
+}
+
 fn make_optional_string(s: &Option<String>) -> String {
     format!("{:?}", s.as_ref().filter(|text| !text.is_empty()))
 }
+
+// Untracked files are only looked up when they are displayed, scanning for them
+// being the most expensive part of git status. Stashes are read from the stash
+// reflog instead. With -z, paths are separated by NUL characters rather than
+// quoted, so that no file name can break the parsing.
+fn make_git_status_args(git: &crate::toml::Git) -> String {
+    let is_set = |s: &Option<String>| s.as_ref().is_some_and(|text| !text.is_empty());
+
+    let mut args = vec!["status", "--porcelain=v2", "--branch", "-z"];
+    if !is_set(&git.untracked) {
+        args.push("--untracked-files=no");
+    }
+    format!("&{args:?}")
+}
+
+// The rewrites are compiled here too, so that a typo in a pattern fails the
+// build rather than every prompt.
+fn make_git_branch(branch: &Option<crate::toml::GitBranch>) -> error::Result<String> {
+    let Some(branch) = branch else {
+        return Ok("None".to_string());
+    };
+
+    let rewrites = branch
+        .rewrites
+        .iter()
+        .map(|rewrite| {
+            Regex::new(&rewrite.pattern).map_err(|source| error::Error::InvalidRegex {
+                pattern: rewrite.pattern.clone(),
+                source,
+            })?;
+            Ok((rewrite.pattern.as_str(), rewrite.replacement.as_str()))
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+    Ok(format!(
+        "Some(GitBranch {{ max_length: {:?}, truncate: GitBranchTruncate::{:?}, ellipsis: {:?}, rewrites: &{rewrites:?} }})",
+        branch.max_length, branch.truncate, branch.ellipsis
+    ))
+}
+
+fn make_git_large_repo(large_repo: &Option<crate::toml::GitLargeRepo>) -> String {
+    let Some(large_repo) = large_repo else {
+        return "None".to_string();
+    };
+
+    format!(
+        "Some(GitLargeRepo {{ file_count: {:?}, paths: {} }})",
+        large_repo.file_count,
+        make_glob_regexes(&large_repo.paths)
+    )
+}
+
+fn make_jj(jj: &Option<crate::toml::Jj>) -> String {
+    let Some(jj) = jj else {
+        return "None".to_string();
+    };
+
+    format!(
+        "Some(Jj {{ bookmarks: {}, conflict: {}, empty: {} }})",
+        make_optional_string(&jj.bookmarks),
+        make_optional_string(&jj.conflict),
+        make_optional_string(&jj.empty)
+    )
+}
+
+fn make_hg(hg: &Option<crate::toml::Hg>) -> String {
+    let Some(hg) = hg else {
+        return "None".to_string();
+    };
+
+    format!(
+        "Some(Hg {{ merge: {}, rebase: {}, histedit: {}, dirty: {}, added: {}, untracked: {} }})",
+        make_optional_string(&hg.merge),
+        make_optional_string(&hg.rebase),
+        make_optional_string(&hg.histedit),
+        make_optional_string(&hg.dirty),
+        make_optional_string(&hg.added),
+        make_optional_string(&hg.untracked)
+    )
+}
+
+fn make_glob_regexes(globs: &[String]) -> String {
+    let regexes: Vec<String> = globs.iter().map(|glob| make_path_regex(glob)).collect();
+    format!("&{regexes:?}")
+}
+
+// Globs are turned into regexes here, so that the prompt doesn't need a glob
+// dependency. "**" matches any number of directories, "*" and "?" match any
+// characters and a single character in a file name.
+fn make_path_regex(glob: &str) -> String {
+    let mut regex = "^".to_string();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c if "\\.+()|[]{}^$".contains(c) => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex.push('$');
+    regex
+}
@@ -15,6 +15,12 @@ pub enum Error {
 
     #[error("Failed to write to config.rs")]
     ConfigRsWrite(#[from] io::Error),
+
+    #[error("Invalid regular expression '{pattern}' in git.branch.rewrites: {source}")]
+    InvalidRegex {
+        pattern: String,
+        source: regex::Error,
+    },
 }
 
 pub trait ErrExt<T, EIn, EOut>
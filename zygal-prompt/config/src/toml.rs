@@ -1,6 +1,6 @@
 use std::{fmt, fs, path::PathBuf};
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, de};
 
 use crate::error::{self, ErrExt};
 
@@ -11,6 +11,8 @@ pub struct Config {
     pub new_line_content: String,
     pub space_around: bool,
     pub git: Git,
+    pub jj: Option<Jj>,
+    pub hg: Option<Hg>,
 }
 
 #[derive(Deserialize)]
@@ -22,15 +24,44 @@ pub enum Shell {
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Git {
+    #[serde(default)]
+    pub detached: GitDetached,
     pub merge: Option<String>,
     pub rebase: Option<String>,
     pub cherry_pick: Option<String>,
     pub revert: Option<String>,
+    pub am: Option<String>,
+    pub bisect: Option<String>,
+    pub sparse: Option<String>,
+    pub partial: Option<String>,
+    pub initial: Option<String>,
+    pub rebase_onto: Option<String>,
+    #[serde(default)]
+    pub protected_branches: Vec<String>,
+    pub conflicted: Option<String>,
     pub unstaged: Option<String>,
     pub staged: Option<String>,
     pub stash: Option<String>,
+    pub branch_stash: Option<String>,
     pub untracked: Option<String>,
+    pub submodules: Option<String>,
+    pub superproject: Option<String>,
+    pub bare: Option<String>,
+    pub inside_git_dir: Option<String>,
     pub remote: Option<GitRemote>,
+    pub large_repo: Option<GitLargeRepo>,
+    pub error: Option<GitError>,
+    pub commit_age: Option<GitCommitAge>,
+    pub branch: Option<GitBranch>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GitDetached {
+    #[default]
+    Sha,
+    Tag,
+    Describe,
 }
 
 #[derive(Deserialize, Debug)]
@@ -40,16 +71,113 @@ pub struct GitRemote {
     pub ahead: String,
     pub behind: String,
     pub on_par: String,
+    pub gone: Option<String>,
+
+    #[serde(default)]
+    pub show_upstream: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+#[allow(dead_code)]
+pub struct GitError {
+    pub dubious_ownership: Option<String>,
+    pub corrupt_index: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+#[allow(dead_code)]
+pub struct GitCommitAge {
+    pub symbol: Option<String>,
+
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub stale_after: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GitBranch {
+    pub max_length: Option<usize>,
+
+    #[serde(default)]
+    pub truncate: GitBranchTruncate,
+
+    #[serde(default = "default_ellipsis")]
+    pub ellipsis: String,
+
+    #[serde(default)]
+    pub rewrites: Vec<GitBranchRewrite>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBranchTruncate {
+    #[default]
+    End,
+    Middle,
+}
+
+#[derive(Deserialize)]
+pub struct GitBranchRewrite {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+fn default_ellipsis() -> String {
+    "…".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GitLargeRepo {
+    pub file_count: Option<u32>,
+
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+#[allow(dead_code)]
+pub struct Jj {
+    pub bookmarks: Option<String>,
+    pub conflict: Option<String>,
+    pub empty: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+#[allow(dead_code)]
+pub struct Hg {
+    pub merge: Option<String>,
+    pub rebase: Option<String>,
+    pub histedit: Option<String>,
+    pub dirty: Option<String>,
+    pub added: Option<String>,
+    pub untracked: Option<String>,
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct ColorScheme {
     pub current_dir: Colors,
-    pub git: Colors,
+    pub git: GitColors,
+    pub git_stale: Option<PartialColors>,
+    pub git_protected: Option<PartialColors>,
     pub new_line: Colors,
 }
 
+#[derive(Deserialize)]
+pub struct GitColors {
+    #[serde(flatten)]
+    pub colors: Colors,
+
+    pub clean: Option<PartialColors>,
+    pub dirty: Option<PartialColors>,
+    pub conflict: Option<PartialColors>,
+}
+
 #[derive(Deserialize)]
 pub struct Colors {
     #[serde(default)]
@@ -59,7 +187,24 @@ pub struct Colors {
     pub foreground: Color,
 }
 
-#[derive(Deserialize, Default)]
+// Colors of the git segment variants, where a missing color falls back to the
+// git segment's own one rather than to "reset".
+#[derive(Deserialize)]
+pub struct PartialColors {
+    pub background: Option<Color>,
+    pub foreground: Option<Color>,
+}
+
+impl PartialColors {
+    pub fn or(&self, colors: &Colors) -> Colors {
+        Colors {
+            background: self.background.unwrap_or(colors.background),
+            foreground: self.foreground.unwrap_or(colors.foreground),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
 pub enum Color {
     #[default]
     #[serde(rename = "reset")]
@@ -69,6 +214,36 @@ pub enum Color {
     AnsiColor(u8),
 }
 
+// Durations such as "30m" or "2d" are turned into seconds, so that the prompt
+// only compares numbers.
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let duration = String::deserialize(deserializer)?;
+    let count = duration.trim_end_matches(char::is_alphabetic);
+    let unit = &duration[count.len()..];
+    let unit_seconds: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(de::Error::custom(format!(
+                "invalid duration '{duration}', expected a number followed by s, m, h, d or w"
+            )));
+        }
+    };
+    let count: u64 = count
+        .parse()
+        .map_err(|_| de::Error::custom(format!("invalid number in duration '{duration}'")))?;
+    count
+        .checked_mul(unit_seconds)
+        .map(Some)
+        .ok_or_else(|| de::Error::custom(format!("duration '{duration}' is too long")))
+}
+
 const PACKAGE_DIR: &str = env!("CARGO_MANIFEST_DIR");
 const TOML: &str = "toml";
 
@@ -18,19 +18,69 @@ new-line-content = "something-short"
 space-around = true
 
 [git]
+detached = "describe"
+rebase-onto = "->"
+protected-branches = ["trunk", "hotfix/**"]
 merge = "@"
 rebase = "_"
 cherry-pick = "|"
 revert = ":"
+am = ";"
+bisect = "/"
+sparse = "<"
+partial = ">"
+initial = "0"
+conflicted = "xx"
 unstaged = "^"
 staged = "&"
 stash = "!"
+branch-stash = "!!!"
 untracked = "??"
+submodules = "<>"
+superproject = "{name}/"
+bare = "bare/"
+inside-git-dir = "!!"
 
 [git.remote]
 ahead = "++"
 behind = "--"
 on-par = "~~"
+gone = "//"
+show-upstream = true
+
+[git.error]
+dubious-ownership = "!owner"
+corrupt-index = "!index"
+
+[git.commit-age]
+symbol = "({age})"
+stale-after = "2w"
+
+[git.branch]
+max-length = 20
+truncate = "middle"
+ellipsis = ".."
+rewrites = [
+    { pattern = "^feature/", replacement = "f/" },
+    { pattern = '^f/(\w+-\d+)-.*', replacement = "$1" },
+]
+
+[git.large-repo]
+file-count = 250000
+paths = ["/src/monorepo", "/home/*/work/**/chromium.git"]
+
+[jj]
+bookmarks = "({name})"
+conflict = "xx"
+empty = "()"
+
+[hg]
+merge = "@"
+rebase = "_"
+histedit = "%"
+dirty = "^"
+added = "&"
+untracked = "??"
 "#,
     );
 
@@ -45,6 +95,25 @@ foreground = 29
 background = 192
 foreground = 0
 
+[git.clean]
+background = 114
+foreground = 0
+
+[git.dirty]
+foreground = 3
+
+[git.conflict]
+background = 203
+foreground = 15
+
+[git-stale]
+background = 196
+foreground = 15
+
+[git-protected]
+background = 160
+foreground = 15
+
 [new-line]
 background = 219
 foreground = 63
@@ -66,6 +135,58 @@ foreground = 63
         pub ahead: &'static str,
         pub behind: &'static str,
         pub on_par: &'static str,
+        pub gone: Option<&'static str>,
+        pub show_upstream: bool,
+    }
+
+    pub struct GitError {
+        pub dubious_ownership: Option<&'static str>,
+        pub corrupt_index: Option<&'static str>,
+    }
+
+    pub struct GitCommitAge {
+        pub symbol: Option<&'static str>,
+        pub stale_after: Option<u64>,
+    }
+
+    pub struct GitBranch {
+        pub max_length: Option<usize>,
+        pub truncate: GitBranchTruncate,
+        pub ellipsis: &'static str,
+        pub rewrites: &'static [(&'static str, &'static str)],
+    }
+
+    pub struct GitLargeRepo {
+        pub file_count: Option<u32>,
+        pub paths: &'static [&'static str],
+    }
+
+    pub struct Jj {
+        pub bookmarks: Option<&'static str>,
+        pub conflict: Option<&'static str>,
+        pub empty: Option<&'static str>,
+    }
+
+    pub struct Hg {
+        pub merge: Option<&'static str>,
+        pub rebase: Option<&'static str>,
+        pub histedit: Option<&'static str>,
+        pub dirty: Option<&'static str>,
+        pub added: Option<&'static str>,
+        pub untracked: Option<&'static str>,
+    }
+
+    #[allow(dead_code)]
+    pub enum GitBranchTruncate {
+        End,
+        Middle,
+    }
+
+    #[allow(dead_code)]
+    pub enum GitDetached {
+        Sha,
+        Tag,
+        Describe,
     }
 
     pub const SHELL: &str = "zsh";
@@ -76,20 +197,50 @@ foreground = 63
 
     pub const GIT_PREFIX: &str = "%F{0}%K{192} ";
     pub const GIT_SUFFIX: &str = " ";
+    pub const GIT_STALE_PREFIX: &str = "%F{15}%K{196} ";
+    pub const GIT_PROTECTED_PREFIX: &str = "%F{15}%K{160} ";
+    pub const GIT_CLEAN_PREFIX: &str = "%F{0}%K{114} ";
+    pub const GIT_DIRTY_PREFIX: &str = "%F{3}%K{192} ";
+    pub const GIT_CONFLICT_PREFIX: &str = "%F{15}%K{203} ";
 
     pub const NEW_LINE: &str = "%F{63}%K{219} something-short ";
 
+    pub const GIT_DETACHED: GitDetached = GitDetached::Describe;
+    pub const GIT_REBASE_ONTO: Option<&str> = Some("->");
+    pub const GIT_PROTECTED_BRANCHES: &[&str] = &["^trunk$", "^hotfix/.*$"];
+
     pub const GIT_MERGE: Option<&str> = Some("@");
     pub const GIT_REBASE: Option<&str> = Some("_");
     pub const GIT_CHERRY_PICK: Option<&str> = Some("|");
     pub const GIT_REVERT: Option<&str> = Some(":");
+    pub const GIT_AM: Option<&str> = Some(";");
+    pub const GIT_BISECT: Option<&str> = Some("/");
+    pub const GIT_SPARSE: Option<&str> = Some("<");
+    pub const GIT_PARTIAL: Option<&str> = Some(">");
+    pub const GIT_INITIAL: Option<&str> = Some("0");
 
+    pub const GIT_CONFLICTED: Option<&str> = Some("xx");
     pub const GIT_UNSTAGED: Option<&str> = Some("^");
     pub const GIT_STAGED: Option<&str> = Some("&");
     pub const GIT_STASH: Option<&str> = Some("!");
+    pub const GIT_BRANCH_STASH: Option<&str> = Some("!!!");
     pub const GIT_UNTRACKED: Option<&str> = Some("??");
+    pub const GIT_SUBMODULES: Option<&str> = Some("<>");
+
+    pub const GIT_SUPERPROJECT: Option<&str> = Some("{name}/");
+    pub const GIT_BARE: Option<&str> = Some("bare/");
+    pub const GIT_INSIDE_GIT_DIR: Option<&str> = Some("!!");
+
+    pub const GIT_REMOTE: Option<GitRemote> = Some(GitRemote { ahead: "++", behind: "--", on_par: "~~", gone: Some("//"), show_upstream: true });
+    pub const GIT_ERROR: Option<GitError> = Some(GitError { dubious_ownership: Some("!owner"), corrupt_index: Some("!index") });
+    pub const GIT_COMMIT_AGE: Option<GitCommitAge> = Some(GitCommitAge { symbol: Some("({age})"), stale_after: Some(1209600) });
+    pub const GIT_BRANCH: Option<GitBranch> = Some(GitBranch { max_length: Some(20), truncate: GitBranchTruncate::Middle, ellipsis: "..", rewrites: &[("^feature/", "f/"), ("^f/(\\w+-\\d+)-.*", "$1")] });
+
+    pub const GIT_STATUS_ARGS: &[&str] = &["status", "--porcelain=v2", "--branch", "-z"];
+    pub const GIT_LARGE_REPO: Option<GitLargeRepo> = Some(GitLargeRepo { file_count: Some(250000), paths: &["^/src/monorepo$", "^/home/[^/]*/work/(?:.*/)?chromium\\.git$"] });
 
-    pub const GIT_REMOTE: Option<GitRemote> = Some(GitRemote { ahead: "++", behind: "--", on_par: "~~" });
+    pub const JJ: Option<Jj> = Some(Jj { bookmarks: Some("({name})"), conflict: Some("xx"), empty: Some("()") });
+    pub const HG: Option<Hg> = Some(Hg { merge: Some("@"), rebase: Some("_"), histedit: Some("%"), dirty: Some("^"), added: Some("&"), untracked: Some("??") });
 }
 "#,
     );
@@ -113,10 +264,25 @@ merge = ""
 rebase = ""
 cherry-pick = ""
 revert = ""
+am = ""
+bisect = ""
+sparse = ""
+partial = ""
+initial = ""
+conflicted = ""
 unstaged = ""
 staged = ""
 stash = ""
+branch-stash = ""
 untracked = ""
+submodules = ""
+superproject = ""
+bare = ""
+inside-git-dir = ""
+
+[git.error]
+dubious-ownership = ""
+corrupt-index = ""
 "#,
     );
 
@@ -129,17 +295,115 @@ untracked = ""
 
     let mut config_output = String::new();
     assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
-    assert_that(config_output).contains(
+    assert_that(&config_output).contains(
         r#"
+    pub const GIT_PROTECTED_BRANCHES: &[&str] = &[];
+
     pub const GIT_MERGE: Option<&str> = None;
     pub const GIT_REBASE: Option<&str> = None;
     pub const GIT_CHERRY_PICK: Option<&str> = None;
     pub const GIT_REVERT: Option<&str> = None;
+    pub const GIT_AM: Option<&str> = None;
+    pub const GIT_BISECT: Option<&str> = None;
+    pub const GIT_SPARSE: Option<&str> = None;
+    pub const GIT_PARTIAL: Option<&str> = None;
+    pub const GIT_INITIAL: Option<&str> = None;
 
+    pub const GIT_CONFLICTED: Option<&str> = None;
     pub const GIT_UNSTAGED: Option<&str> = None;
     pub const GIT_STAGED: Option<&str> = None;
     pub const GIT_STASH: Option<&str> = None;
+    pub const GIT_BRANCH_STASH: Option<&str> = None;
     pub const GIT_UNTRACKED: Option<&str> = None;
+    pub const GIT_SUBMODULES: Option<&str> = None;
+
+    pub const GIT_SUPERPROJECT: Option<&str> = None;
+    pub const GIT_BARE: Option<&str> = None;
+    pub const GIT_INSIDE_GIT_DIR: Option<&str> = None;
+"#,
+    );
+    assert_that(config_output).contains(
+        r#"
+    pub const GIT_ERROR: Option<GitError> = Some(GitError { dubious_ownership: None, corrupt_index: None });
+"#,
+    );
+}
+
+#[test]
+fn empty_string_in_jj_symbols_is_none() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[jj]
+bookmarks = ""
+conflict = ""
+empty = ""
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const JJ: Option<Jj> = Some(Jj { bookmarks: None, conflict: None, empty: None });
+"#,
+    );
+}
+
+#[test]
+fn empty_string_in_hg_symbols_is_none() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[hg]
+merge = ""
+rebase = ""
+histedit = ""
+dirty = ""
+added = ""
+untracked = ""
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const HG: Option<Hg> = Some(Hg { merge: None, rebase: None, histedit: None, dirty: None, added: None, untracked: None });
 "#,
     );
 }
@@ -177,6 +441,11 @@ space-around = false
 
     pub const GIT_PREFIX: &str = "%F{0}%K{0}";
     pub const GIT_SUFFIX: &str = "";
+    pub const GIT_STALE_PREFIX: &str = "%F{0}%K{0}";
+    pub const GIT_PROTECTED_PREFIX: &str = "%F{0}%K{0}";
+    pub const GIT_CLEAN_PREFIX: &str = "%F{0}%K{0}";
+    pub const GIT_DIRTY_PREFIX: &str = "%F{0}%K{0}";
+    pub const GIT_CONFLICT_PREFIX: &str = "%F{0}%K{0}";
 
     pub const NEW_LINE: &str = "%F{0}%K{0}%#";
 "#,
@@ -210,21 +479,396 @@ space-around = true
 
     let mut config_output = String::new();
     assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
-    assert_that(config_output).contains(
+    assert_that(&config_output).contains(
         r#"
+    pub const GIT_PROTECTED_BRANCHES: &[&str] = &[];
+
     pub const GIT_MERGE: Option<&str> = None;
     pub const GIT_REBASE: Option<&str> = None;
     pub const GIT_CHERRY_PICK: Option<&str> = None;
     pub const GIT_REVERT: Option<&str> = None;
+    pub const GIT_AM: Option<&str> = None;
+    pub const GIT_BISECT: Option<&str> = None;
+    pub const GIT_SPARSE: Option<&str> = None;
+    pub const GIT_PARTIAL: Option<&str> = None;
+    pub const GIT_INITIAL: Option<&str> = None;
 
+    pub const GIT_CONFLICTED: Option<&str> = None;
     pub const GIT_UNSTAGED: Option<&str> = None;
     pub const GIT_STAGED: Option<&str> = None;
     pub const GIT_STASH: Option<&str> = None;
+    pub const GIT_BRANCH_STASH: Option<&str> = None;
     pub const GIT_UNTRACKED: Option<&str> = None;
+    pub const GIT_SUBMODULES: Option<&str> = None;
+
+    pub const GIT_SUPERPROJECT: Option<&str> = None;
+    pub const GIT_BARE: Option<&str> = None;
+    pub const GIT_INSIDE_GIT_DIR: Option<&str> = None;
 
     pub const GIT_REMOTE: Option<GitRemote> = None;
+    pub const GIT_ERROR: Option<GitError> = None;
+    pub const GIT_COMMIT_AGE: Option<GitCommitAge> = None;
+    pub const GIT_BRANCH: Option<GitBranch> = None;
+
+    pub const GIT_STATUS_ARGS: &[&str] = &["status", "--porcelain=v2", "--branch", "-z", "--untracked-files=no"];
+    pub const GIT_LARGE_REPO: Option<GitLargeRepo> = None;
+
+    pub const JJ: Option<Jj> = None;
+    pub const HG: Option<Hg> = None;
+"#,
+    );
+    assert_that(&config_output).contains("pub const GIT_DETACHED: GitDetached = GitDetached::Sha;");
+}
+
+#[test]
+fn optional_git_remote_keys_default_to_none_and_false() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.remote]
+ahead = ">"
+behind = "<"
+on-par = "="
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const GIT_REMOTE: Option<GitRemote> = Some(GitRemote { ahead: ">", behind: "<", on_par: "=", gone: None, show_upstream: false });
+"#,
+    );
+}
+
+#[test]
+fn optional_git_large_repo_keys_default_to_none_and_empty() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.large-repo]
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const GIT_LARGE_REPO: Option<GitLargeRepo> = Some(GitLargeRepo { file_count: None, paths: &[] });
+"#,
+    );
+}
+
+#[test]
+fn optional_git_commit_age_stale_after_defaults_to_none() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.commit-age]
+symbol = "{age}"
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const GIT_COMMIT_AGE: Option<GitCommitAge> = Some(GitCommitAge { symbol: Some("{age}"), stale_after: None });
+"#,
+    );
+}
+
+#[test]
+fn error_when_git_commit_age_stale_after_is_invalid() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.commit-age]
+symbol = "{age}"
+stale-after = "3 days"
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_err();
+}
+
+#[test]
+fn git_commit_age_stale_after_accepts_seconds() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.commit-age]
+symbol = "{age}"
+stale-after = "90s"
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const GIT_COMMIT_AGE: Option<GitCommitAge> = Some(GitCommitAge { symbol: Some("{age}"), stale_after: Some(90) });
+"#,
+    );
+}
+
+#[test]
+fn git_commit_age_is_none_without_symbol_nor_stale_after() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.commit-age]
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output)
+        .contains("\n    pub const GIT_COMMIT_AGE: Option<GitCommitAge> = None;\n");
+}
+
+#[test]
+fn error_when_git_commit_age_stale_after_overflows() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.commit-age]
+symbol = "{age}"
+stale-after = "18446744073709551615w"
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_err();
+}
+
+#[test]
+fn optional_git_branch_keys_default_to_end_ellipsis_and_no_rewrites() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.branch]
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(config_output).contains(
+        r#"
+    pub const GIT_BRANCH: Option<GitBranch> = Some(GitBranch { max_length: None, truncate: GitBranchTruncate::End, ellipsis: "…", rewrites: &[] });
+"#,
+    );
+}
+
+#[test]
+fn error_when_git_branch_rewrite_pattern_is_invalid() {
+    let tmp_dir = temp_dir();
+    let color_scheme = color_scheme_toml(&tmp_dir);
+    let config_output_path = create_temp_file(&tmp_dir);
+
+    let config_input = write_temp_file(
+        &tmp_dir,
+        r#"
+shell = "zsh"
+new-line-content = "%#"
+space-around = true
+
+[git]
+
+[git.branch]
+rewrites = [{ pattern = '^(\w+-\d+', replacement = "$1" }]
+"#,
+    );
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result)
+        .err()
+        .mapping(|err| err.to_string())
+        .starts_with(r"Invalid regular expression '^(\w+-\d+' in git.branch.rewrites");
+}
+
+#[test]
+fn partial_git_colors_default_to_git_colors() {
+    let tmp_dir = temp_dir();
+    let config_input = config_toml(&tmp_dir);
+
+    let color_scheme = write_temp_file(
+        &tmp_dir,
+        r#"
+[current-dir]
+
+[git]
+background = 220
+foreground = 0
+
+[git.dirty]
+background = 214
+
+[git.conflict]
+foreground = "reset"
+
+[git-stale]
+background = 196
+
+[git-protected]
+foreground = 15
+
+[new-line]
 "#,
     );
+
+    let mut config_output_path = create_temp_file(&tmp_dir);
+
+    let write_config_result = write_config(
+        config_output_path.path(),
+        &config_input.to_string(),
+        &color_scheme.to_string(),
+    );
+    assert_that(write_config_result).is_ok();
+
+    let mut config_output = String::new();
+    assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
+    assert_that(&config_output).contains(r#"pub const GIT_STALE_PREFIX: &str = "%F{0}%K{196} ";"#);
+    assert_that(&config_output)
+        .contains(r#"pub const GIT_PROTECTED_PREFIX: &str = "%F{15}%K{220} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_CLEAN_PREFIX: &str = "%F{0}%K{220} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_DIRTY_PREFIX: &str = "%F{0}%K{214} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_CONFLICT_PREFIX: &str = "%f%K{220} ";"#);
 }
 
 #[test]
@@ -259,6 +903,11 @@ foreground = "reset"
     assert_that(config_output_path.read_to_string(&mut config_output)).is_ok();
     assert_that(&config_output).contains(r#"pub const CURRENT_DIR_PREFIX: &str = "%F{22}%k ";"#);
     assert_that(&config_output).contains(r#"pub const GIT_PREFIX: &str = "%f%K{7} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_STALE_PREFIX: &str = "%f%K{7} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_PROTECTED_PREFIX: &str = "%f%K{7} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_CLEAN_PREFIX: &str = "%f%K{7} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_DIRTY_PREFIX: &str = "%f%K{7} ";"#);
+    assert_that(&config_output).contains(r#"pub const GIT_CONFLICT_PREFIX: &str = "%f%K{7} ";"#);
     assert_that(&config_output).contains(r#"pub const NEW_LINE: &str = "%f%k %# ";"#);
 }
 
@@ -9,6 +9,7 @@ fn main() -> process::ExitCode {
                 zygal_config::Error::TomlNotFound { .. } => 80,
                 zygal_config::Error::TomlParse(_) => 81,
                 zygal_config::Error::ConfigRsWrite(_) => 82,
+                zygal_config::Error::InvalidRegex { .. } => 83,
             })
         }
     }
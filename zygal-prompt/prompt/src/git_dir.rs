@@ -0,0 +1,472 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct GitDir(PathBuf);
+
+impl GitDir {
+    pub fn new(path: PathBuf) -> Self {
+        Self(path)
+    }
+
+    // Like git, GIT_DIR takes precedence over looking up .git, while
+    // GIT_WORK_TREE doesn't affect where the git directory is.
+    pub fn find(current_dir: &Path) -> Option<Self> {
+        Self::discover(current_dir, env::var_os("GIT_DIR"))
+    }
+
+    // Bare repositories and the inside of git directories have no .git to
+    // find, but then the current directory or one of its parents is a git
+    // directory itself. It's cheaper to check than running git rev-parse.
+    pub fn encloses(current_dir: &Path) -> bool {
+        Self::encloses_with(current_dir, env::var_os("GIT_DIR"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    // The index starts with the "DIRC" signature, the index version and the
+    // number of entries, which is roughly the number of tracked files.
+    pub fn index_entry_count(&self) -> Option<u32> {
+        let mut header = [0; 12];
+        fs::File::open(self.0.join("index"))
+            .ok()?
+            .read_exact(&mut header)
+            .ok()?;
+        if &header[..4] != b"DIRC" {
+            return None;
+        }
+        Some(u32::from_be_bytes([
+            header[8], header[9], header[10], header[11],
+        ]))
+    }
+
+    // Sparse checkouts are enabled in the repository or worktree config, and
+    // their patterns are in the worktree's own git directory. Git reads its
+    // config itself, as it can be spread over included and global files.
+    pub fn is_sparse(&self) -> bool {
+        self.0.join("info/sparse-checkout").exists()
+            && process::Command::new("git")
+                .arg("--git-dir")
+                .arg(&self.0)
+                .args(["config", "--type=bool", "--get", "core.sparseCheckout"])
+                .output()
+                .is_ok_and(|output| output.stdout == b"true\n")
+    }
+
+    // Packs fetched from promisor remotes come with a .promisor file, so
+    // partial clones have at least one of them.
+    pub fn is_partial(&self) -> bool {
+        fs::read_dir(self.common_dir().join("objects/pack")).is_ok_and(|entries| {
+            entries.filter_map(Result::ok).any(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == "promisor")
+            })
+        })
+    }
+
+    // Stashes are shared by all worktrees, and each of them has a line in the
+    // stash reflog, with the stash message after a tab.
+    pub fn stash_messages(&self) -> Vec<String> {
+        let Ok(stash_log) = fs::read_to_string(self.common_dir().join("logs/refs/stash")) else {
+            return Vec::new();
+        };
+        stash_log
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(_, message)| message.to_string())
+            .collect()
+    }
+
+    // Linked worktrees share the config of the main git directory, which is
+    // pointed to by their "commondir" file.
+    fn common_dir(&self) -> PathBuf {
+        match fs::read_to_string(self.0.join("commondir")) {
+            Ok(common_dir) => self.0.join(common_dir.trim()),
+            Err(_) => self.0.clone(),
+        }
+    }
+
+    // Submodules have their git directory in the "modules" directory of the
+    // superproject's git directory, as in "super/.git/modules/sub". Their name
+    // can contain "modules" too, so that directory only counts when it's right
+    // inside a git directory.
+    pub fn superproject_name(&self) -> Option<String> {
+        let git_dir = fs::canonicalize(&self.0).ok()?;
+        let superproject_git_dir = git_dir
+            .ancestors()
+            .skip(1)
+            .filter(|dir| dir.file_name().is_some_and(|name| name == "modules"))
+            .filter_map(Path::parent)
+            .find(|dir| Self::is_git_dir(dir))?;
+
+        // Nested submodules are in the "modules" directory of their parent
+        // submodule's git directory, which is named after the parent itself.
+        let superproject_dir = if superproject_git_dir
+            .file_name()
+            .is_some_and(|name| name == ".git")
+        {
+            superproject_git_dir.parent()?
+        } else {
+            superproject_git_dir
+        };
+        Some(superproject_dir.file_name()?.to_string_lossy().into_owned())
+    }
+
+    fn encloses_with(current_dir: &Path, git_dir_env: Option<OsString>) -> bool {
+        git_dir_env.is_some_and(|git_dir| !git_dir.is_empty())
+            || current_dir.ancestors().any(Self::is_git_dir)
+    }
+
+    // Like git, a directory with a HEAD file next to the objects and refs
+    // directories is considered a git directory.
+    fn is_git_dir(dir: &Path) -> bool {
+        dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+    }
+
+    fn discover(current_dir: &Path, git_dir_env: Option<OsString>) -> Option<Self> {
+        if let Some(git_dir) = git_dir_env.filter(|git_dir| !git_dir.is_empty()) {
+            return Some(Self(current_dir.join(git_dir)));
+        }
+
+        current_dir
+            .ancestors()
+            .map(|dir| dir.join(".git"))
+            .find_map(Self::resolve)
+    }
+
+    fn resolve(dot_git: PathBuf) -> Option<Self> {
+        if dot_git.is_dir() {
+            return Some(Self(dot_git));
+        }
+
+        // Linked worktrees and submodules have a .git file pointing to their
+        // own git directory, which is where their in-progress state files are.
+        // The path can be relative to the .git file.
+        let content = fs::read_to_string(&dot_git).ok()?;
+        let git_dir = content.strip_prefix("gitdir:")?.trim();
+        let dot_git_parent = dot_git.parent()?;
+        Some(Self(dot_git_parent.join(git_dir)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use asserting::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finds_dot_git_directory_in_ancestors() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        let current_dir = tmp_dir.path().join("nested/deeply");
+        create_dir(&git_dir);
+        create_dir(&current_dir);
+
+        let found = GitDir::discover(&current_dir, None);
+        assert_that(found).has_value(GitDir(git_dir));
+    }
+
+    #[test]
+    fn follows_relative_gitdir_in_dot_git_file() {
+        let tmp_dir = mktemp();
+        let current_dir = tmp_dir.path().join("submodule");
+        create_dir(&current_dir);
+        write_file(
+            &current_dir.join(".git"),
+            "gitdir: ../.git/modules/submodule\n",
+        );
+
+        let found = GitDir::discover(&current_dir, None);
+        assert_that(found).has_value(GitDir(current_dir.join("../.git/modules/submodule")));
+    }
+
+    #[test]
+    fn follows_absolute_gitdir_in_dot_git_file() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join("repo/.git/worktrees/linked");
+        let current_dir = tmp_dir.path().join("linked");
+        create_dir(&current_dir);
+        write_file(
+            &current_dir.join(".git"),
+            &format!("gitdir: {}\n", git_dir.display()),
+        );
+
+        let found = GitDir::discover(&current_dir, None);
+        assert_that(found).has_value(GitDir(git_dir));
+    }
+
+    #[test]
+    fn uses_git_dir_environment_variable() {
+        let tmp_dir = mktemp();
+        create_dir(&tmp_dir.path().join(".git"));
+
+        let found = GitDir::discover(tmp_dir.path(), Some("elsewhere.git".into()));
+        assert_that(found).has_value(GitDir(tmp_dir.path().join("elsewhere.git")));
+    }
+
+    #[test]
+    fn finds_superproject_name_of_submodule() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join("platform/.git/modules/vendored");
+        make_git_dir(&tmp_dir.path().join("platform/.git"));
+        make_git_dir(&git_dir);
+
+        let superproject_name = GitDir(git_dir).superproject_name();
+        assert_that(superproject_name).has_value("platform");
+    }
+
+    #[test]
+    fn finds_superproject_name_of_nested_submodule() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir
+            .path()
+            .join("platform/.git/modules/vendored/modules/nested");
+        make_git_dir(&tmp_dir.path().join("platform/.git"));
+        make_git_dir(&tmp_dir.path().join("platform/.git/modules/vendored"));
+        make_git_dir(&git_dir);
+
+        let superproject_name = GitDir(git_dir).superproject_name();
+        assert_that(superproject_name).has_value("vendored");
+    }
+
+    #[test]
+    fn finds_superproject_name_of_submodule_with_modules_in_its_path() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir
+            .path()
+            .join("platform/.git/modules/third_party/modules/foo");
+        make_git_dir(&tmp_dir.path().join("platform/.git"));
+        make_git_dir(&git_dir);
+
+        let superproject_name = GitDir(git_dir).superproject_name();
+        assert_that(superproject_name).has_value("platform");
+    }
+
+    #[test]
+    fn no_superproject_name_when_not_submodule() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join("platform/.git");
+        make_git_dir(&git_dir);
+
+        let superproject_name = GitDir(git_dir).superproject_name();
+        assert_that(superproject_name).is_none();
+    }
+
+    #[test]
+    fn reads_index_entry_count() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        create_dir(&git_dir);
+        fs::write(
+            git_dir.join("index"),
+            b"DIRC\x00\x00\x00\x02\x00\x01\x86\xa0remaining-entries",
+        )
+        .expect("Failed to write index in git_dir tests");
+
+        assert_that(GitDir(git_dir).index_entry_count()).has_value(100_000);
+    }
+
+    #[test]
+    fn no_index_entry_count_when_not_an_index() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        create_dir(&git_dir);
+        fs::write(git_dir.join("index"), b"not an index")
+            .expect("Failed to write index in git_dir tests");
+
+        assert_that(GitDir(git_dir).index_entry_count()).is_none();
+    }
+
+    #[test]
+    fn sparse_when_sparse_checkout_is_enabled() {
+        let config_lines = [
+            "sparseCheckout = true",
+            "sparseCheckout = yes",
+            "sparseCheckout = on",
+            "sparseCheckout = 1",
+            "sparseCheckout",
+            "sparseCheckout = true ; cone mode",
+            "sparseCheckout = \"true\"",
+        ];
+        for config_line in config_lines {
+            let tmp_dir = mktemp();
+            let git_dir = make_sparse_git_dir(&tmp_dir);
+            write_file(
+                &git_dir.join("config"),
+                &format!("[core]\n\t{config_line}\n"),
+            );
+
+            assert_that(GitDir(git_dir).is_sparse())
+                .named(config_line)
+                .is_true();
+        }
+    }
+
+    #[test]
+    fn sparse_when_sparse_checkout_is_enabled_in_worktree_config() {
+        let tmp_dir = mktemp();
+        let git_dir = make_sparse_git_dir(&tmp_dir);
+        write_file(
+            &git_dir.join("config"),
+            "[core]\n\trepositoryFormatVersion = 1\n[extensions]\n\tworktreeConfig = true\n",
+        );
+        write_file(
+            &git_dir.join("config.worktree"),
+            "[core]\n\tsparseCheckout = true\n\tsparseCheckoutCone = true\n",
+        );
+
+        assert_that(GitDir(git_dir).is_sparse()).is_true();
+    }
+
+    #[test]
+    fn sparse_when_sparse_checkout_is_enabled_in_included_config() {
+        let tmp_dir = mktemp();
+        let git_dir = make_sparse_git_dir(&tmp_dir);
+        write_file(&git_dir.join("config"), "[include]\n\tpath = sparse.inc\n");
+        write_file(&git_dir.join("sparse.inc"), "[core]\n\tsparseCheckout\n");
+
+        assert_that(GitDir(git_dir).is_sparse()).is_true();
+    }
+
+    #[test]
+    fn not_sparse_when_sparse_checkout_is_disabled() {
+        let tmp_dir = mktemp();
+        let git_dir = make_sparse_git_dir(&tmp_dir);
+        write_file(&git_dir.join("config"), "[core]\n\tsparseCheckout = off\n");
+
+        assert_that(GitDir(git_dir).is_sparse()).is_false();
+    }
+
+    #[test]
+    fn not_sparse_without_sparse_checkout_patterns() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        make_git_dir(&git_dir);
+        write_file(&git_dir.join("config"), "[core]\n\tsparseCheckout = true\n");
+
+        assert_that(GitDir(git_dir).is_sparse()).is_false();
+    }
+
+    #[test]
+    fn partial_when_promisor_pack_in_common_dir() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        let worktree_git_dir = git_dir.join("worktrees/feature");
+        create_dir(&worktree_git_dir);
+        create_dir(&git_dir.join("objects/pack"));
+        write_file(&worktree_git_dir.join("commondir"), "../..\n");
+        write_file(&git_dir.join("objects/pack/pack-1f2e3d4.promisor"), "");
+
+        assert_that(GitDir(worktree_git_dir).is_partial()).is_true();
+    }
+
+    #[test]
+    fn not_partial_without_promisor_pack() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        create_dir(&git_dir.join("objects/pack"));
+        write_file(&git_dir.join("objects/pack/pack-1f2e3d4.pack"), "");
+
+        assert_that(GitDir(git_dir).is_partial()).is_false();
+    }
+
+    #[test]
+    fn reads_stash_messages_in_common_dir() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join(".git");
+        let worktree_git_dir = git_dir.join("worktrees/feature");
+        create_dir(&worktree_git_dir);
+        create_dir(&git_dir.join("logs/refs"));
+        write_file(&worktree_git_dir.join("commondir"), "../..\n");
+        write_file(
+            &git_dir.join("logs/refs/stash"),
+            "0000000 1f2e3d4 Charles Darwin <charles.darwin@downe.uk> 1700000000 +0000\tWIP on main: 26fc77b Add finches\n\
+             1f2e3d4 5a6b7c8 Charles Darwin <charles.darwin@downe.uk> 1700000100 +0000\tOn feature/x: beaks\n",
+        );
+
+        assert_that(GitDir(worktree_git_dir).stash_messages()).is_equal_to(vec![
+            "WIP on main: 26fc77b Add finches".to_string(),
+            "On feature/x: beaks".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn no_stash_messages_without_stash_reflog() {
+        let tmp_dir = mktemp();
+        assert_that(GitDir(tmp_dir.path().to_path_buf()).stash_messages()).is_empty();
+    }
+
+    #[test]
+    fn encloses_bare_repository_subdirectory() {
+        let tmp_dir = mktemp();
+        let git_dir = tmp_dir.path().join("repo.git");
+        make_git_dir(&git_dir);
+        create_dir(&git_dir.join("refs/heads"));
+
+        assert_that(GitDir::encloses_with(&git_dir.join("refs/heads"), None)).is_true();
+    }
+
+    #[test]
+    fn encloses_when_git_dir_environment_variable_is_set() {
+        let tmp_dir = mktemp();
+        let encloses = GitDir::encloses_with(tmp_dir.path(), Some("elsewhere.git".into()));
+        assert_that(encloses).is_true();
+    }
+
+    #[test]
+    fn does_not_enclose_directory_outside_git_dir() {
+        let tmp_dir = mktemp();
+        create_dir(&tmp_dir.path().join("objects"));
+        create_dir(&tmp_dir.path().join("refs"));
+
+        assert_that(GitDir::encloses_with(tmp_dir.path(), None)).is_false();
+    }
+
+    #[test]
+    fn none_when_no_dot_git() {
+        let tmp_dir = mktemp();
+        let found = GitDir::discover(tmp_dir.path(), None);
+        assert_that(found).is_none();
+    }
+
+    fn mktemp() -> TempDir {
+        TempDir::with_prefix("zygal-git-dir-test")
+            .expect("Failed to create temporary directory in git dir tests")
+    }
+
+    fn make_sparse_git_dir(tmp_dir: &TempDir) -> PathBuf {
+        let git_dir = tmp_dir.path().join(".git");
+        make_git_dir(&git_dir);
+        create_dir(&git_dir.join("info"));
+        write_file(&git_dir.join("info/sparse-checkout"), "/*\n!/*/\n");
+        git_dir
+    }
+
+    fn make_git_dir(path: &Path) {
+        create_dir(&path.join("objects"));
+        create_dir(&path.join("refs"));
+        write_file(&path.join("HEAD"), "ref: refs/heads/main\n");
+    }
+
+    fn create_dir(path: &Path) {
+        let err_msg = format!("Failed to create directory {path:?} in git dir tests");
+        fs::create_dir_all(path).expect(&err_msg);
+    }
+
+    fn write_file(path: &Path, content: &str) {
+        let err_msg = format!("Failed to write to file {path:?} in git dir tests");
+        fs::write(path, content).expect(&err_msg);
+    }
+}
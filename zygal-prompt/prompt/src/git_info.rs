@@ -1,166 +1,773 @@
-use std::{fmt::Display, path::Path, process, str::FromStr, sync::LazyLock};
+use std::{
+    fmt::Display,
+    fs,
+    path::Path,
+    process,
+    str::FromStr,
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Context;
 use regex::Regex;
 
-use crate::config;
+use crate::{
+    config,
+    git_dir::GitDir,
+    git_patch::GitPatch,
+    porcelain::{Entry, FileState, Head, Oid, Status},
+    symbol::Symbol,
+    vcs::{VcsDivergence, VcsDivergenceSymbols, VcsState, VcsStatus},
+};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Default)]
 pub struct GitInfo {
     pub branch_name: String,
+    pub detached: bool,
+    // No commit yet, as in freshly initialized repositories
+    pub unborn: bool,
+    // Seconds since HEAD was committed
+    pub commit_age: Option<u64>,
+    // git status is skipped, so the working tree isn't looked at
+    pub large_repo: bool,
     pub remote_diff: Option<GitRemoteDiff>,
-    pub stash: bool,
-    pub untracked: bool,
-    pub staged: bool,
-    pub unstaged: bool,
+    pub stash: usize,
+    pub branch_stash: usize,
+    pub untracked: usize,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub conflicted: usize,
+    pub submodules: usize,
+    pub superproject: Option<String>,
+    pub sparse: bool,
+    pub partial: bool,
+    pub internals: Option<GitInternals>,
+    pub failure: Option<GitFailure>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GitInternals {
+    Bare,
+    InsideGitDir,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GitFailure {
+    DubiousOwnership,
+    CorruptIndex,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct GitRemoteDiff {
-    pub incoming: bool,
-    pub outgoing: bool,
+    pub upstream: String,
+    pub incoming: u64,
+    pub outgoing: u64,
+    pub gone: bool,
 }
 
-static STAGED_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^[12u] [MTARCDU].").expect("Static regex is valid"));
-static UNSTAGED_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^[12u] .[MTARCDU]").expect("Static regex is valid"));
+static LARGE_REPO_PATH_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    config::GIT_LARGE_REPO
+        .map(|large_repo| large_repo.paths)
+        .unwrap_or_default()
+        .iter()
+        .map(|path| Regex::new(path).expect("Large repository path regex is valid"))
+        .collect()
+});
+static BRANCH_REWRITE_REGEXES: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
+    config::GIT_BRANCH
+        .map(|branch| branch.rewrites)
+        .unwrap_or_default()
+        .iter()
+        .map(|(pattern, replacement)| {
+            let regex = Regex::new(pattern).expect("Branch rewrite regex is checked at build time");
+            (regex, *replacement)
+        })
+        .collect()
+});
+static PROTECTED_BRANCH_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    config::GIT_PROTECTED_BRANCHES
+        .iter()
+        .map(|branch| Regex::new(branch).expect("Protected branch regex is valid"))
+        .collect()
+});
 
 impl FromStr for GitInfo {
     type Err = anyhow::Error;
 
     fn from_str(git_status_output: &str) -> anyhow::Result<Self> {
-        let mut lines = git_status_output.lines().map(str::trim);
+        Self::from_status(git_status_output.parse()?)
+    }
+}
 
-        let branch_name = Self::make_branch_name(&mut lines)?;
+impl GitInfo {
+    fn from_status(status: Status) -> anyhow::Result<Self> {
+        let (branch_name, detached) = Self::make_branch_name(&status)?;
+        let unborn = status.oid == Some(Oid::Initial);
 
-        let lines: Vec<&str> = lines.collect();
-        let stash = lines.any_if_some(config::GIT_STASH, |l| l.starts_with("# stash"));
-        let untracked = lines.any_if_some(config::GIT_UNTRACKED, |l| l.starts_with("?"));
-        let staged = lines.any_if_some(config::GIT_STAGED, |l| STAGED_REGEX.is_match(l));
-        let unstaged = lines.any_if_some(config::GIT_UNSTAGED, |l| UNSTAGED_REGEX.is_match(l));
-        // Can't use .and because it's not const
+        let entries = &status.entries;
+        let untracked =
+            entries.count_if_some(config::GIT_UNTRACKED, |e| matches!(e, Entry::Untracked(_)));
+        let staged = entries.count_if_some(config::GIT_STAGED, |e| {
+            e.changed()
+                .is_some_and(|c| c.xy.index != FileState::Unmodified)
+        });
+        // Submodules with new commits, modified content or untracked files
+        let is_changed_submodule = |e: &Entry| e.submodule().is_some_and(|s| s.is_changed());
+        // Submodule changes are only unstaged when not shown on their own
+        let unstaged = entries.count_if_some(config::GIT_UNSTAGED, |e| {
+            e.changed()
+                .is_some_and(|c| c.xy.worktree != FileState::Unmodified)
+                && !(config::GIT_SUBMODULES.is_some() && is_changed_submodule(e))
+        });
+        // Always counted, as they make the state a conflict even when their
+        // symbol isn't shown
+        let conflicted = entries
+            .iter()
+            .filter(|e| matches!(e, Entry::Unmerged(_)))
+            .count();
+        let submodules = entries.count_if_some(config::GIT_SUBMODULES, is_changed_submodule);
         let remote_diff = if config::GIT_REMOTE.is_some() {
-            GitRemoteDiff::parse(lines.iter().copied())?
+            GitRemoteDiff::from_status(&status)
         } else {
             None
         };
 
         Ok(Self {
             branch_name,
+            detached,
+            unborn,
             remote_diff,
-            stash,
             untracked,
             staged,
             unstaged,
+            conflicted,
+            submodules,
+            ..Self::default()
         })
     }
-}
 
-impl GitInfo {
-    pub fn from_git_status_output(current_dir: &Path) -> anyhow::Result<Option<Self>> {
+    pub fn from_git_status_output(
+        current_dir: &Path,
+        git_dir: Option<&GitDir>,
+    ) -> anyhow::Result<Option<Self>> {
+        let large_repo_git_dir = git_dir.filter(|git_dir| {
+            config::GIT_LARGE_REPO.is_some_and(|large_repo| {
+                Self::is_large_repo(
+                    current_dir,
+                    git_dir,
+                    large_repo.file_count,
+                    &LARGE_REPO_PATH_REGEXES,
+                )
+            })
+        });
+        // git status is still run when HEAD can't be read on its own
+        let large_repo_git_info =
+            large_repo_git_dir.and_then(|git_dir| Self::from_head(git_dir).ok());
+        let mut git_info = if let Some(mut git_info) = large_repo_git_info {
+            git_info.large_repo = true;
+            git_info
+        } else {
+            let output = process::Command::new("git")
+                .args(config::GIT_STATUS_ARGS)
+                // Failures are classified from their English messages
+                .env("LC_ALL", "C")
+                .current_dir(current_dir)
+                .output()
+                .context("Failed to spawn subprocess to execute git status")?;
+
+            if output.status.success() {
+                // Paths aren't quoted with -z, so they may not be valid UTF-8
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                Self::from_status(Status::from_nul_separated(&stdout)?)?
+            } else if let Some(failure) = GitFailure::classify(&output.stderr)
+                && let Some(git_dir) = git_dir
+            {
+                // Whatever went wrong, the branch can usually still be read
+                // from HEAD. When even that fails, as in repositories of other
+                // users, there is no segment like before git status failed.
+                let Ok(mut git_info) = Self::from_head(git_dir) else {
+                    return Ok(None);
+                };
+                git_info.failure = Some(failure);
+                git_info
+            } else if let Some(git_info) = Self::from_git_internals(current_dir)? {
+                git_info
+            } else {
+                return Ok(None);
+            }
+        };
+
+        // Reading the stash reflog is cheaper than git status --show-stash,
+        // and tells which branch each stash was made on.
+        if (config::GIT_STASH.is_some() || config::GIT_BRANCH_STASH.is_some())
+            && let Some(git_dir) = git_dir
+        {
+            let stash_messages = git_dir.stash_messages();
+            git_info.stash = stash_messages.len();
+            if !git_info.detached {
+                git_info.branch_stash =
+                    Self::count_branch_stashes(&stash_messages, &git_info.branch_name);
+            }
+        }
+        if config::GIT_COMMIT_AGE.is_some() && !git_info.unborn {
+            git_info.commit_age = Self::read_commit_age(current_dir)?;
+        }
+        if config::GIT_SUPERPROJECT.is_some() {
+            git_info.superproject = git_dir.and_then(GitDir::superproject_name);
+        }
+        if config::GIT_SPARSE.is_some() {
+            git_info.sparse = git_dir.is_some_and(GitDir::is_sparse);
+        }
+        if config::GIT_PARTIAL.is_some() {
+            git_info.partial = git_dir.is_some_and(GitDir::is_partial);
+        }
+        if !git_info.detached {
+            return Ok(Some(git_info));
+        }
+        // HEAD is detached during rebases, but the branch being rebased is more
+        // useful than whatever HEAD points to.
+        if let Some(git_dir) = git_dir
+            && let Some(name) =
+                Self::make_rebase_branch_name(git_dir, config::GIT_REBASE_ONTO, |sha| {
+                    Self::name_rev(sha, current_dir)
+                })?
+        {
+            git_info.branch_name = name;
+        } else if !matches!(config::GIT_DETACHED, config::GitDetached::Sha)
+            && let Some(name) = Self::describe_head(current_dir, &config::GIT_DETACHED)?
+        {
+            git_info.branch_name = format!("({name})");
+        }
+        Ok(Some(git_info))
+    }
+
+    pub fn into_vcs_status(self, git_patches: &[GitPatch]) -> VcsStatus {
+        if let Some(GitInternals::InsideGitDir) = self.internals
+            && let Some(inside_git_dir_symbol) = config::GIT_INSIDE_GIT_DIR
+        {
+            return VcsStatus::new(inside_git_dir_symbol.to_string());
+        }
+
+        let mut head = String::new();
+        if let Some(GitInternals::Bare) = self.internals
+            && let Some(bare_symbol) = config::GIT_BARE
+        {
+            head.push_str(bare_symbol);
+        }
+        if let Some(superproject_symbol) = config::GIT_SUPERPROJECT
+            && let Some(superproject) = self.superproject.as_ref()
+        {
+            head.push_str(&superproject_symbol.with_name(superproject));
+        }
+        head.push_str(&Self::make_short_branch_name(&self.branch_name));
+
+        let mut vcs_status = VcsStatus::new(head);
+        if !self.large_repo && self.failure.is_none() && self.internals.is_none() {
+            vcs_status.state = Some(self.make_vcs_state(git_patches));
+        }
+        // A detached HEAD isn't on any branch, even while rebasing one
+        vcs_status.protected =
+            !self.detached && Self::is_protected(&self.branch_name, &PROTECTED_BRANCH_REGEXES);
+        if let Some(config::GitRemote {
+            show_upstream: true,
+            ..
+        }) = config::GIT_REMOTE
+            && let Some(remote_diff) = self.remote_diff.as_ref()
+        {
+            vcs_status.upstream = Some(remote_diff.upstream.clone());
+        }
+
+        if let Some(failure) = self.failure.as_ref() {
+            vcs_status.states.push(failure.to_string());
+        }
+        vcs_status
+            .states
+            .extend(git_patches.iter().map(GitPatch::to_string));
+        vcs_status.push_state(config::GIT_SPARSE, self.sparse);
+        vcs_status.push_state(config::GIT_PARTIAL, self.partial);
+        vcs_status.push_state(config::GIT_INITIAL, self.unborn);
+
+        vcs_status.push_change(config::GIT_CONFLICTED, self.conflicted);
+        vcs_status.push_change(config::GIT_UNSTAGED, self.unstaged);
+        vcs_status.push_change(config::GIT_STAGED, self.staged);
+        vcs_status.push_change(config::GIT_STASH, self.stash);
+        vcs_status.push_change(config::GIT_BRANCH_STASH, self.branch_stash);
+        vcs_status.push_change(config::GIT_UNTRACKED, self.untracked);
+        vcs_status.push_change(config::GIT_SUBMODULES, self.submodules);
+
+        if let Some(config::GitRemote {
+            ahead,
+            behind,
+            on_par,
+            gone,
+            ..
+        }) = config::GIT_REMOTE
+            && let Some(remote_diff) = self.remote_diff
+        {
+            vcs_status.divergence = Some(remote_diff.into_vcs_divergence(VcsDivergenceSymbols {
+                ahead,
+                behind,
+                on_par,
+                gone,
+            }));
+        }
+        if let Some(config::GitCommitAge {
+            symbol,
+            stale_after,
+        }) = config::GIT_COMMIT_AGE
+            && let Some(commit_age) = self.commit_age
+        {
+            vcs_status.commit_age =
+                symbol.map(|symbol| symbol.with_age(&make_age(commit_age)).into_owned());
+            vcs_status.stale = stale_after.is_some_and(|stale_after| commit_age >= stale_after);
+        }
+        vcs_status
+    }
+
+    // In-progress operations other than bisect leave the repository halfway
+    // through a change, but it's only conflicted once unmerged files are left.
+    fn make_vcs_state(&self, git_patches: &[GitPatch]) -> VcsState {
+        let in_progress = git_patches
+            .iter()
+            .any(|git_patch| !matches!(git_patch, GitPatch::Bisect));
+        if self.conflicted != 0 {
+            VcsState::Conflict
+        } else if in_progress || self.staged + self.unstaged + self.untracked + self.submodules != 0
+        {
+            VcsState::Dirty
+        } else {
+            VcsState::Clean
+        }
+    }
+
+    fn is_protected(branch_name: &str, protected_branch_regexes: &[Regex]) -> bool {
+        protected_branch_regexes
+            .iter()
+            .any(|regex| regex.is_match(branch_name))
+    }
+
+    fn make_short_branch_name(branch_name: &str) -> String {
+        let Some(config::GitBranch {
+            max_length,
+            truncate,
+            ellipsis,
+            ..
+        }) = config::GIT_BRANCH
+        else {
+            return branch_name.to_string();
+        };
+
+        let branch_name = Self::rewrite_branch_name(branch_name, &BRANCH_REWRITE_REGEXES);
+        match max_length {
+            Some(max_length) => {
+                Self::truncate_branch_name(&branch_name, max_length, &truncate, ellipsis)
+            }
+            None => branch_name,
+        }
+    }
+
+    fn rewrite_branch_name(branch_name: &str, rewrites: &[(Regex, &str)]) -> String {
+        rewrites
+            .iter()
+            .fold(branch_name.to_string(), |name, (regex, replacement)| {
+                regex.replace_all(&name, *replacement).into_owned()
+            })
+    }
+
+    // Lengths are in characters, and the ellipsis counts towards the maximum
+    fn truncate_branch_name(
+        branch_name: &str,
+        max_length: usize,
+        truncate: &config::GitBranchTruncate,
+        ellipsis: &str,
+    ) -> String {
+        let length = branch_name.chars().count();
+        if length <= max_length {
+            return branch_name.to_string();
+        }
+
+        // Even the ellipsis is cut when it doesn't fit in the max length
+        let ellipsis: String = ellipsis.chars().take(max_length).collect();
+        let kept = max_length - ellipsis.chars().count();
+        // The start gets the extra character when it can't be split evenly
+        let (start_length, end_length) = match truncate {
+            config::GitBranchTruncate::End => (kept, 0),
+            config::GitBranchTruncate::Middle => (kept.div_ceil(2), kept / 2),
+        };
+        let start: String = branch_name.chars().take(start_length).collect();
+        let end: String = branch_name.chars().skip(length - end_length).collect();
+        format!("{start}{ellipsis}{end}")
+    }
+
+    fn is_large_repo(
+        current_dir: &Path,
+        git_dir: &GitDir,
+        file_count: Option<u32>,
+        path_regexes: &[Regex],
+    ) -> bool {
+        let is_large_path = current_dir.ancestors().any(|dir| {
+            let dir = dir.to_string_lossy();
+            path_regexes.iter().any(|regex| regex.is_match(&dir))
+        });
+        is_large_path
+            || file_count.is_some_and(|file_count| {
+                git_dir
+                    .index_entry_count()
+                    .is_some_and(|count| count >= file_count)
+            })
+    }
+
+    // Large repositories skip git status entirely and only show the branch,
+    // which is read straight from HEAD.
+    fn from_head(git_dir: &GitDir) -> anyhow::Result<Self> {
+        let head_path = git_dir.path().join("HEAD");
+        let head = fs::read_to_string(&head_path)
+            .with_context(|| format!("Failed to read {}", head_path.display()))?;
+        let head = head.trim();
+
+        let (branch_name, detached) = if let Some(branch_ref) = head.strip_prefix("ref:") {
+            let branch_ref = branch_ref.trim();
+            // The reftable backend keeps the actual HEAD in its tables
+            if branch_ref == "refs/heads/.invalid" {
+                anyhow::bail!("HEAD is stored in reftables");
+            }
+            let branch_name = branch_ref.strip_prefix("refs/heads/").unwrap_or(branch_ref);
+            (branch_name.to_string(), false)
+        } else {
+            let sha = head.get(..7).context("Malformed HEAD in git directory")?;
+            (format!("({sha}...)"), true)
+        };
+
+        Ok(Self {
+            branch_name,
+            detached,
+            ..Self::default()
+        })
+    }
+
+    // Like in __git_ps1, bare repositories and git directories get their own
+    // git segment, since git status fails there.
+    fn from_git_internals(current_dir: &Path) -> anyhow::Result<Option<Self>> {
+        // Can't use .and because it's not const
+        if config::GIT_BARE.is_none() && config::GIT_INSIDE_GIT_DIR.is_none() {
+            return Ok(None);
+        }
+        if !GitDir::encloses(current_dir) {
+            return Ok(None);
+        }
+
         let output = process::Command::new("git")
-            .args(["status", "--porcelain=v2", "--branch", "--show-stash"])
+            .args([
+                "rev-parse",
+                "--git-dir",
+                "--is-inside-git-dir",
+                "--is-bare-repository",
+            ])
             .current_dir(current_dir)
             .output()
-            .context("Failed to spawn subprocess to execute git status")?;
+            .context("Failed to spawn subprocess to execute git rev-parse")?;
 
         if !output.status.success() {
             return Ok(None);
         }
 
         let stdout = String::from_utf8(output.stdout)
-            .context("Failed to read git status output as an UTF-8 string")?;
-        stdout.parse::<Self>().map(Some)
+            .context("Failed to read git rev-parse output as an UTF-8 string")?;
+        let mut lines = stdout.lines();
+        let git_dir = lines
+            .next()
+            .context("Missing git directory in git rev-parse output")?;
+        let inside_git_dir = lines.next() == Some("true");
+        let bare = lines.next() == Some("true");
+
+        // Bare repositories are also inside their git directory
+        let internals = match (bare, inside_git_dir) {
+            (true, _) if config::GIT_BARE.is_some() => GitInternals::Bare,
+            (false, true) if config::GIT_INSIDE_GIT_DIR.is_some() => GitInternals::InsideGitDir,
+            _ => return Ok(None),
+        };
+
+        let mut git_info = Self::from_head(&GitDir::new(current_dir.join(git_dir)))?;
+        git_info.internals = Some(internals);
+        Ok(Some(git_info))
     }
 
-    fn make_branch_name<'a>(
-        git_status_lines: &mut impl Iterator<Item = &'a str>,
+    fn make_rebase_branch_name(
+        git_dir: &GitDir,
+        onto_symbol: Option<&str>,
+        name_rev: impl FnOnce(&str) -> anyhow::Result<Option<String>>,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(rebase_dir) = ["rebase-merge", "rebase-apply"]
+            .iter()
+            .map(|rebase_dir| git_dir.path().join(rebase_dir))
+            .find(|rebase_dir| rebase_dir.join("head-name").exists())
+        else {
+            return Ok(None);
+        };
+
+        let head_name = fs::read_to_string(rebase_dir.join("head-name"))
+            .context("Failed to read the name of the branch being rebased")?;
+        // Rebasing a detached HEAD writes "detached HEAD" instead of a ref
+        let Some(branch_name) = head_name.trim().strip_prefix("refs/heads/") else {
+            return Ok(None);
+        };
+        let Some(onto_symbol) = onto_symbol else {
+            return Ok(Some(branch_name.to_string()));
+        };
+
+        let onto = fs::read_to_string(rebase_dir.join("onto"))
+            .context("Failed to read the commit the branch is rebased onto")?;
+        let onto = onto.trim();
+        let onto_name = match name_rev(onto)? {
+            Some(onto_name) => onto_name,
+            None => abbreviate_sha(onto).to_string(),
+        };
+        Ok(Some(format!("{branch_name}{onto_symbol}{onto_name}")))
+    }
+
+    fn read_commit_age(current_dir: &Path) -> anyhow::Result<Option<u64>> {
+        let output = process::Command::new("git")
+            .args(["log", "-1", "--format=%ct"])
+            .current_dir(current_dir)
+            .output()
+            .context("Failed to spawn subprocess to execute git log")?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .context("Failed to read git log output as an UTF-8 string")?;
+        let commit_time = stdout
+            .trim()
+            .parse::<u64>()
+            .context("Failed to parse commit time in git log output")?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System time is before the Unix epoch")?
+            .as_secs();
+        // Commits from the future, with a skewed clock, are just committed
+        Ok(Some(now.saturating_sub(commit_time)))
+    }
+
+    fn name_rev(sha: &str, current_dir: &Path) -> anyhow::Result<Option<String>> {
+        let output = process::Command::new("git")
+            .args([
+                "name-rev",
+                "--name-only",
+                "--no-undefined",
+                "--refs=refs/heads/*",
+                sha,
+            ])
+            .current_dir(current_dir)
+            .output()
+            .context("Failed to spawn subprocess to execute git name-rev")?;
+
+        // No branch contains the commit
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .context("Failed to read git name-rev output as an UTF-8 string")?;
+        Ok(Some(stdout.trim().to_string()))
+    }
+
+    fn describe_head(
+        current_dir: &Path,
+        detached: &config::GitDetached,
+    ) -> anyhow::Result<Option<String>> {
+        let args: &[&str] = match detached {
+            config::GitDetached::Sha => return Ok(None),
+            config::GitDetached::Tag => &["describe", "--tags", "--exact-match"],
+            config::GitDetached::Describe => &["describe", "--tags", "--long"],
+        };
+        let output = process::Command::new("git")
+            .args(args)
+            .current_dir(current_dir)
+            .output()
+            .context("Failed to spawn subprocess to execute git describe")?;
+
+        // No tag is reachable from HEAD, or HEAD is not on a tag
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .context("Failed to read git describe output as an UTF-8 string")?;
+        Self::make_detached_name(stdout.trim(), detached).map(Some)
+    }
+
+    fn make_detached_name(
+        git_describe_output: &str,
+        detached: &config::GitDetached,
     ) -> anyhow::Result<String> {
-        let sha = git_status_lines
-            .next()
-            .context("Missing # branch.oid header in git status output")?
-            .split_whitespace()
-            .nth(2)
-            .context("Malformed # branch.oid header in git status output")?;
+        if !matches!(detached, config::GitDetached::Describe) {
+            return Ok(git_describe_output.to_string());
+        }
 
-        let branch_name = git_status_lines
+        // Tags can contain dashes, so the output is split from the end
+        let mut parts = git_describe_output.rsplitn(3, '-').skip(1);
+        let distance = parts
+            .next()
+            .context("Missing distance from tag in git describe output")?;
+        let tag = parts
             .next()
-            .context("Missing # branch.head header in git status output")?
-            .split_whitespace()
-            .nth(2)
-            .context("Malformed # branch.head header in git status output")?;
+            .context("Missing tag name in git describe output")?;
 
-        Ok(if branch_name == "(detached)" {
-            format!("({}...)", &sha[..7])
+        Ok(if distance == "0" {
+            tag.to_string()
         } else {
-            branch_name.to_string()
+            format!("{tag}~{distance}")
+        })
+    }
+
+    // git stash names stashes after the branch they were made on, as in
+    // "WIP on main: 26fc77b Add finches" or "On main: finches".
+    fn count_branch_stashes(stash_messages: &[String], branch_name: &str) -> usize {
+        let wip_prefix = format!("WIP on {branch_name}:");
+        let prefix = format!("On {branch_name}:");
+        stash_messages
+            .iter()
+            .filter(|message| message.starts_with(&wip_prefix) || message.starts_with(&prefix))
+            .count()
+    }
+
+    fn make_branch_name(status: &Status) -> anyhow::Result<(String, bool)> {
+        let oid = status
+            .oid
+            .as_ref()
+            .context("Missing # branch.oid header in git status output")?;
+        let head = status
+            .head
+            .as_ref()
+            .context("Missing # branch.head header in git status output")?;
+
+        Ok(match (head, oid) {
+            (Head::Branch(branch_name), _) => (branch_name.clone(), false),
+            (Head::Detached, Oid::Initial) => ("(initial)".to_string(), true),
+            (Head::Detached, Oid::Commit(sha)) => (format!("({}...)", abbreviate_sha(sha)), true),
         })
     }
 }
 
 impl GitRemoteDiff {
-    fn parse<'a>(
-        mut git_status_lines: impl Iterator<Item = &'a str>,
-    ) -> anyhow::Result<Option<Self>> {
-        let Some(ab_line) = git_status_lines.find(|l| l.starts_with("# branch.ab")) else {
-            return Ok(None);
+    fn from_status(status: &Status) -> Option<Self> {
+        let upstream = status.upstream.clone()?;
+
+        // git omits # branch.ab when the upstream branch doesn't exist anymore
+        let Some(ahead_behind) = status.ahead_behind.as_ref() else {
+            return Some(Self {
+                upstream,
+                incoming: 0,
+                outgoing: 0,
+                gone: true,
+            });
         };
 
-        let mut counts = ab_line.split_whitespace().skip(2);
-        let outgoing_count = counts
-            .next()
-            .context("Missing outgoing count in git status # branch.ab header")?
-            .parse::<i64>()
-            .context("Failed to parse outgoing count in git status # branch.ab header")?;
-        let incoming_count = counts
-            .next()
-            .context("Missing incoming count in git status # branch.ab header")?
-            .parse::<i64>()
-            .context("Failed to parse incoming count in git status # branch.ab header")?;
+        Some(Self {
+            upstream,
+            incoming: ahead_behind.behind,
+            outgoing: ahead_behind.ahead,
+            gone: false,
+        })
+    }
 
-        Ok(Some(Self {
-            incoming: incoming_count != 0,
-            outgoing: outgoing_count != 0,
-        }))
+    fn into_vcs_divergence(self, symbols: VcsDivergenceSymbols) -> VcsDivergence {
+        VcsDivergence {
+            ahead: self.outgoing,
+            behind: self.incoming,
+            gone: self.gone,
+            symbols,
+        }
     }
 }
 
-impl Display for GitRemoteDiff {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let config::GitRemote {
-            ahead,
-            behind,
-            on_par,
-        } = config::GIT_REMOTE.expect("[git.remote] not configured");
+impl GitFailure {
+    // Only failures with a configured symbol are reported, the other ones are
+    // treated as not being in a repository.
+    fn classify(git_stderr: &[u8]) -> Option<Self> {
+        let config::GitError {
+            dubious_ownership,
+            corrupt_index,
+        } = config::GIT_ERROR?;
+        let stderr = String::from_utf8_lossy(git_stderr);
 
-        if !self.incoming && !self.outgoing {
-            return write!(f, "{on_par}");
+        if dubious_ownership.is_some() && stderr.contains("detected dubious ownership") {
+            Some(Self::DubiousOwnership)
+        } else if corrupt_index.is_some()
+            && [
+                "index file corrupt",
+                "index file smaller than expected",
+                "bad signature",
+            ]
+            .iter()
+            .any(|message| stderr.contains(message))
+        {
+            Some(Self::CorruptIndex)
+        } else {
+            None
         }
+    }
+}
 
-        if self.incoming {
-            write!(f, "{behind}")?;
-        }
-        if self.outgoing {
-            write!(f, "{ahead}")?;
-        }
-        Ok(())
+impl Display for GitFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let config::GitError {
+            dubious_ownership,
+            corrupt_index,
+        } = config::GIT_ERROR.expect("GitFailure is only created when git.error is set");
+        let symbol = match self {
+            Self::DubiousOwnership => dubious_ownership,
+            Self::CorruptIndex => corrupt_index,
+        };
+        let symbol = symbol.unwrap_or_else(|| panic!("Unknown GitFailure {self:?}"));
+        write!(f, "{symbol}")
+    }
+}
+
+// Both SHA-1 and SHA-256 object names are abbreviated to git's default length
+// of 7 bytes. Shorter names, and the ones that can't be cut on a character
+// boundary there, are kept whole.
+fn abbreviate_sha(sha: &str) -> &str {
+    sha.get(..7).unwrap_or(sha)
+}
+
+// Only the largest unit is kept, as in "45s", "12m", "3d" or "2y"
+fn make_age(seconds: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    match seconds {
+        s if s < MINUTE => format!("{s}s"),
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < WEEK => format!("{}d", s / DAY),
+        s if s < YEAR => format!("{}w", s / WEEK),
+        s => format!("{}y", s / YEAR),
     }
 }
 
-trait Lines {
-    fn any_if_some<T, F>(&self, s: Option<T>, f: F) -> bool
+trait Entries {
+    fn count_if_some<T, F>(&self, s: Option<T>, f: F) -> usize
     where
-        F: Fn(&&str) -> bool;
+        F: Fn(&Entry) -> bool;
 }
 
-impl Lines for Vec<&str> {
+impl Entries for Vec<Entry> {
     #[inline]
-    fn any_if_some<T, F>(&self, s: Option<T>, f: F) -> bool
+    fn count_if_some<T, F>(&self, s: Option<T>, f: F) -> usize
     where
-        F: Fn(&&str) -> bool,
+        F: Fn(&Entry) -> bool,
     {
-        s.is_some() && self.iter().any(f)
+        if s.is_some() {
+            self.iter().filter(|e| f(e)).count()
+        } else {
+            0
+        }
     }
 }
 
@@ -169,13 +776,13 @@ mod tests {
     use super::*;
 
     use asserting::prelude::*;
+    use tempfile::TempDir;
 
     #[test]
     fn error_when_no_branch_info() {
-        let status_output = "
-            # stash number-not-used
-        "
-        .trim();
+        let status_output = "\
+# branch.upstream origin/main
+";
         let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info).is_err();
     }
@@ -184,12 +791,12 @@ mod tests {
     fn uses_branch_name_when_not_detached_head() {
         let branch_name = "feature/honey-badgers";
         let status_output = format!(
-            "
-            # branch.oid unused-invalid-sha
-            # branch.head {branch_name}
-            "
+            "\
+# branch.oid unused-invalid-sha
+# branch.head {branch_name}
+"
         );
-        let git_info = status_output.trim().parse::<GitInfo>();
+        let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info)
             .ok()
             .mapping(|g| g.branch_name)
@@ -198,11 +805,10 @@ mod tests {
 
     #[test]
     fn uses_commit_sha_when_detached_head() {
-        let status_output = "
-            # branch.oid faeddf84c5077e7df0025334801d379bb94fc64f
-            # branch.head (detached)
-        "
-        .trim();
+        let status_output = "\
+# branch.oid faeddf84c5077e7df0025334801d379bb94fc64f
+# branch.head (detached)
+";
         let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info)
             .ok()
@@ -211,225 +817,952 @@ mod tests {
     }
 
     #[test]
-    fn includes_stashes() {
-        let status_output = "
-            # branch.oid unused-invalid-sha
-            # branch.head feature/spider-monkey
-            # stash number-not-used
-        "
-        .trim();
+    fn uses_initial_when_detached_head_without_commits() {
+        let status_output = "\
+# branch.oid (initial)
+# branch.head (detached)
+";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info)
+            .ok()
+            .mapping(|g| g.branch_name)
+            .is_equal_to("(initial)");
+    }
+
+    #[test]
+    fn uses_commit_sha_when_detached_head_in_sha256_repository() {
+        let status_output = "\
+# branch.oid 6c1f0a35b2d7c1d6f3d3e0b9b3a0d2f1c6e4a9b8d7c6f5e4d3c2b1a0f9e8d7c6
+# branch.head (detached)
+";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info)
+            .ok()
+            .mapping(|g| g.branch_name)
+            .is_equal_to("(6c1f0a3...)");
+    }
+
+    #[test]
+    fn uses_whole_sha_when_detached_head_with_short_sha() {
+        let status_output = "\
+# branch.oid fa3
+# branch.head (detached)
+";
         let git_info = status_output.parse::<GitInfo>();
-        assert_that(git_info).ok().mapping(|g| g.stash).is_true();
+        assert_that(git_info)
+            .ok()
+            .mapping(|g| g.branch_name)
+            .is_equal_to("(fa3...)");
+    }
+
+    #[test]
+    fn marks_unborn_branch() {
+        let status_output = "\
+# branch.oid (initial)
+# branch.head main
+";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info).ok().mapping(|g| g.unborn).is_true();
+    }
+
+    #[test]
+    fn marks_detached_head() {
+        let status_output = "\
+# branch.oid faeddf84c5077e7df0025334801d379bb94fc64f
+# branch.head (detached)
+";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info).ok().mapping(|g| g.detached).is_true();
+    }
+
+    #[test]
+    fn uses_tag_name_when_detached_on_tag() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        let git = |args: &[&str]| {
+            let status = process::Command::new("git")
+                .args(["-c", "user.name=Charles Darwin"])
+                .args(["-c", "user.email=charles.darwin@downe.uk"])
+                .args(args)
+                .current_dir(tmp_dir.path())
+                .output()
+                .expect("Failed to execute git in git info tests")
+                .status;
+            assert!(
+                status.success(),
+                "Failed to execute git {args:?} in git info tests"
+            );
+        };
+        git(&["init", "--quiet"]);
+        git(&[
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "--message",
+            "Add finches",
+        ]);
+        git(&["tag", "v1.2.0"]);
+        git(&["switch", "--quiet", "--detach", "v1.2.0"]);
+
+        let detached_name = GitInfo::describe_head(tmp_dir.path(), &config::GitDetached::Tag);
+        assert_that(detached_name).ok().has_value("v1.2.0");
+    }
+
+    #[test]
+    fn uses_tag_name_when_describing_head_on_tag() {
+        let detached_name =
+            GitInfo::make_detached_name("v1.2.0-0-gfaeddf8", &config::GitDetached::Describe);
+        assert_that(detached_name).has_value("v1.2.0");
+    }
+
+    #[test]
+    fn uses_tag_name_and_distance_when_describing_head_after_tag() {
+        let detached_name = GitInfo::make_detached_name(
+            "release-v1.2.0-3-gfaeddf8",
+            &config::GitDetached::Describe,
+        );
+        assert_that(detached_name).has_value("release-v1.2.0~3");
+    }
+
+    #[test]
+    fn error_when_malformed_describe_output() {
+        let detached_name = GitInfo::make_detached_name("v1.2.0", &config::GitDetached::Describe);
+        assert_that(detached_name).is_err();
+    }
+
+    #[test]
+    fn uses_rebased_branch_and_onto_name() {
+        let tmp_dir = make_rebase_merge_dir();
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let branch_name = GitInfo::make_rebase_branch_name(&git_dir, Some("→"), |sha| {
+            assert_that(sha).is_equal_to("26fc77b95e7e8ace0608d20a811d5b2cad292feb");
+            Ok(Some("main".to_string()))
+        });
+        assert_that(branch_name).ok().has_value("feature/x→main");
+    }
+
+    #[test]
+    fn uses_rebased_branch_and_onto_sha_when_onto_has_no_name() {
+        let tmp_dir = make_rebase_merge_dir();
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let branch_name = GitInfo::make_rebase_branch_name(&git_dir, Some("→"), |_| Ok(None));
+        assert_that(branch_name).ok().has_value("feature/x→26fc77b");
+    }
+
+    #[test]
+    fn uses_only_rebased_branch_without_onto_symbol() {
+        let tmp_dir = make_rebase_merge_dir();
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let branch_name = GitInfo::make_rebase_branch_name(&git_dir, None, |_| {
+            panic!("The rebase onto name is only looked up with an onto symbol")
+        });
+        assert_that(branch_name).ok().has_value("feature/x");
+    }
+
+    fn make_rebase_merge_dir() -> TempDir {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        let rebase_dir = tmp_dir.path().join("rebase-merge");
+        fs::create_dir(&rebase_dir).expect("Failed to create rebase-merge in git info tests");
+        fs::write(rebase_dir.join("head-name"), "refs/heads/feature/x\n")
+            .expect("Failed to write head-name in git info tests");
+        fs::write(
+            rebase_dir.join("onto"),
+            "26fc77b95e7e8ace0608d20a811d5b2cad292feb\n",
+        )
+        .expect("Failed to write onto in git info tests");
+        tmp_dir
+    }
+
+    #[test]
+    fn large_repo_when_index_has_at_least_file_count_entries() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        fs::write(
+            tmp_dir.path().join("index"),
+            b"DIRC\x00\x00\x00\x02\x00\x01\x86\xa0remaining-entries",
+        )
+        .expect("Failed to write index in git info tests");
+
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let is_large =
+            |file_count| GitInfo::is_large_repo(tmp_dir.path(), &git_dir, file_count, &[]);
+        assert_that(is_large(Some(100_000))).is_true();
+        assert_that(is_large(Some(100_001))).is_false();
+        assert_that(is_large(None)).is_false();
+    }
+
+    #[test]
+    fn large_repo_when_parent_dir_matches_double_star_glob() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        let tmp_path = regex::escape(&tmp_dir.path().to_string_lossy());
+        // Generated from "<tmp_dir>/**/chromium"
+        let path_regexes = [Regex::new(&format!("^{tmp_path}/(?:.*/)?chromium$"))
+            .expect("Invalid regex in git info tests")];
+
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let is_large = |current_dir: &str| {
+            let current_dir = tmp_dir.path().join(current_dir);
+            GitInfo::is_large_repo(&current_dir, &git_dir, None, &path_regexes)
+        };
+        assert_that(is_large("chromium")).is_true();
+        assert_that(is_large("work/browsers/chromium/src/base")).is_true();
+        assert_that(is_large("work/chromium-fork/src")).is_false();
+    }
+
+    #[test]
+    fn large_repo_when_parent_dir_matches_star_glob() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        let tmp_path = regex::escape(&tmp_dir.path().to_string_lossy());
+        // Generated from "<tmp_dir>/work/*/monorepo"
+        let path_regexes = [Regex::new(&format!("^{tmp_path}/work/[^/]*/monorepo$"))
+            .expect("Invalid regex in git info tests")];
+
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let is_large = |current_dir: &str| {
+            let current_dir = tmp_dir.path().join(current_dir);
+            GitInfo::is_large_repo(&current_dir, &git_dir, None, &path_regexes)
+        };
+        assert_that(is_large("work/darwin/monorepo/services")).is_true();
+        assert_that(is_large("work/darwin/nested/monorepo")).is_false();
+    }
+
+    #[test]
+    fn uses_branch_name_from_head_in_large_repo() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        fs::write(tmp_dir.path().join("HEAD"), "ref: refs/heads/feature/x\n")
+            .expect("Failed to write HEAD in git info tests");
+
+        let git_info = GitInfo::from_head(&GitDir::new(tmp_dir.path().to_path_buf()));
+        assert_that(git_info).has_value(GitInfo {
+            branch_name: "feature/x".to_string(),
+            ..GitInfo::default()
+        });
+    }
+
+    #[test]
+    fn error_when_head_is_stored_in_reftables() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        fs::write(tmp_dir.path().join("HEAD"), "ref: refs/heads/.invalid\n")
+            .expect("Failed to write HEAD in git info tests");
+
+        let git_info = GitInfo::from_head(&GitDir::new(tmp_dir.path().to_path_buf()));
+        assert_that(git_info).is_err();
+    }
+
+    #[test]
+    fn error_when_head_is_malformed() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        fs::write(tmp_dir.path().join("HEAD"), "26fc\n")
+            .expect("Failed to write HEAD in git info tests");
+
+        let git_info = GitInfo::from_head(&GitDir::new(tmp_dir.path().to_path_buf()));
+        assert_that(git_info).is_err();
+    }
+
+    #[test]
+    fn uses_commit_sha_from_head_in_large_repo() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        fs::write(
+            tmp_dir.path().join("HEAD"),
+            "26fc77b95e7e8ace0608d20a811d5b2cad292feb\n",
+        )
+        .expect("Failed to write HEAD in git info tests");
+
+        let git_info = GitInfo::from_head(&GitDir::new(tmp_dir.path().to_path_buf()));
+        assert_that(git_info).has_value(GitInfo {
+            branch_name: "(26fc77b...)".to_string(),
+            detached: true,
+            ..GitInfo::default()
+        });
+    }
+
+    #[test]
+    fn no_rebased_branch_when_rebasing_detached_head() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        let rebase_dir = tmp_dir.path().join("rebase-merge");
+        fs::create_dir(&rebase_dir).expect("Failed to create rebase-merge in git info tests");
+        fs::write(rebase_dir.join("head-name"), "detached HEAD\n")
+            .expect("Failed to write head-name in git info tests");
+
+        let git_dir = GitDir::new(tmp_dir.path().to_path_buf());
+        let branch_name = GitInfo::make_rebase_branch_name(&git_dir, Some("→"), |_| Ok(None));
+        assert_that(branch_name).ok().is_none();
+    }
+
+    #[test]
+    fn reads_stashes_from_stash_reflog() {
+        let tmp_dir = TempDir::with_prefix("zygal-git-info-test")
+            .expect("Failed to create temporary directory in git info tests");
+        let git = |args: &[&str]| {
+            let status = process::Command::new("git")
+                .args(["-c", "user.name=Charles Darwin"])
+                .args(["-c", "user.email=charles.darwin@downe.uk"])
+                .args(args)
+                .current_dir(tmp_dir.path())
+                .output()
+                .expect("Failed to execute git in git info tests")
+                .status;
+            assert!(
+                status.success(),
+                "Failed to execute git {args:?} in git info tests"
+            );
+        };
+        git(&["init", "--quiet", "--initial-branch", "feature/x"]);
+        git(&[
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "--message",
+            "Add finches",
+        ]);
+        let git_dir = GitDir::new(tmp_dir.path().join(".git"));
+        let unused_sha = "26fc77b95e7e8ace0608d20a811d5b2cad292feb";
+        fs::create_dir_all(git_dir.path().join("logs/refs"))
+            .expect("Failed to create logs/refs in git info tests");
+        fs::write(
+            git_dir.path().join("logs/refs/stash"),
+            format!(
+                "{unused_sha} {unused_sha} Charles Darwin <charles.darwin@downe.uk> 1 +0000\tOn main: beaks\n\
+                 {unused_sha} {unused_sha} Charles Darwin <charles.darwin@downe.uk> 2 +0000\tWIP on feature/x: 26fc77b Add finches\n"
+            ),
+        )
+        .expect("Failed to write stash reflog in git info tests");
+
+        let git_info = GitInfo::from_git_status_output(tmp_dir.path(), Some(&git_dir))
+            .expect("Failed to read git info in git info tests")
+            .expect("Failed to find git info in git info tests");
+        assert_that(git_info.stash).is_equal_to(2);
+        assert_that(git_info.branch_stash).is_equal_to(1);
+
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.to_string()).is_equal_to("feature/x $&");
+    }
+
+    #[test]
+    fn counts_stashes_made_on_branch() {
+        let stash_messages = [
+            "WIP on feature/x: 26fc77b Add finches".to_string(),
+            "On main: beaks".to_string(),
+            "On feature/x: beaks".to_string(),
+            "WIP on feature/xy: 26fc77b Add finches".to_string(),
+            "WIP on (no branch): 26fc77b Add finches".to_string(),
+        ];
+        let branch_stash = GitInfo::count_branch_stashes(&stash_messages, "feature/x");
+        assert_that(branch_stash).is_equal_to(2);
     }
 
     #[test]
     fn includes_untracked() {
-        let status_output = "
-            # branch.oid unused-invalid-sha
-            # branch.head feature/hippopotamus
-            ? path-not-used
-        "
-        .trim();
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/hippopotamus
+? path-not-used
+? other-path-not-used
+";
         let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info)
             .ok()
             .mapping(|g| g.untracked)
-            .is_true();
+            .is_equal_to(2);
+    }
+
+    #[test]
+    fn includes_untracked_with_whitespace_only_name() {
+        let status_output =
+            "# branch.oid unused-invalid-sha\n# branch.head feature/hydrochoerus\n?  \n";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info)
+            .ok()
+            .mapping(|g| g.untracked)
+            .is_equal_to(1);
     }
 
     #[test]
     fn includes_staged() {
-        let status_output = "
-            # branch.oid unused-invalid-sha
-            # branch.head feature/gorgonopsia
-            1 Dwhatever
-        "
-        .trim();
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/gorgonopsia
+1 D. N... 100644 100644 100644 unused-sha unused-sha whatever
+";
         let git_info = status_output.parse::<GitInfo>();
-        assert_that(git_info).ok().mapping(|g| g.staged).is_true();
+        assert_that(git_info)
+            .ok()
+            .mapping(|g| g.staged)
+            .is_equal_to(1);
     }
 
     #[test]
     fn includes_unstaged() {
-        let status_output = "
-            # branch.oid unused-invalid-sha
-            # branch.head feature/microraptoria
-            1 WD
-        "
-        .trim();
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/microraptoria
+1 .D N... 100644 100644 100644 unused-sha unused-sha path-not-used
+";
         let git_info = status_output.parse::<GitInfo>();
-        assert_that(git_info).ok().mapping(|g| g.unstaged).is_true();
+        assert_that(git_info)
+            .ok()
+            .mapping(|g| g.unstaged)
+            .is_equal_to(1);
     }
 
     #[test]
     fn includes_branch_staged_untracked_incoming_remote() {
-        let status_output = "
-            # branch.oid unused-invalid-sha
-            # branch.head feature/monothremes
-            # branch.ab +0 -12
-            2 D.
-            1 M.
-            ? not-used-file-name
-        "
-        .trim();
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/monothremes
+# branch.upstream origin/feature/monothremes
+# branch.ab +0 -12
+2 R. N... 100644 100644 100644 unused-sha unused-sha R100 new-path\told-path
+1 M. N... 100644 100644 100644 unused-sha unused-sha path-not-used
+? not-used-file-name
+";
         let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info).has_value(GitInfo {
             branch_name: "feature/monothremes".to_string(),
-            staged: true,
-            untracked: true,
+            staged: 2,
+            untracked: 1,
             remote_diff: Some(GitRemoteDiff {
-                incoming: true,
-                outgoing: false,
+                upstream: "origin/feature/monothremes".to_string(),
+                incoming: 12,
+                outgoing: 0,
+                gone: false,
             }),
-            stash: false,
-            unstaged: false,
+            ..GitInfo::default()
         });
     }
 
     #[test]
-    fn includes_sha_stash_unstaged_on_par_remote() {
-        let status_output = "
-            # branch.oid dfcac0b563036735405591415163566f9f908e1e
-            # branch.head (detached)
-            # branch.ab +0 -0
-            # stash number-not-used
-            u .D
-            2 .R
-        "
-        .trim();
+    fn includes_sha_conflicted_unstaged_on_par_remote() {
+        let status_output = "\
+# branch.oid dfcac0b563036735405591415163566f9f908e1e
+# branch.head (detached)
+# branch.upstream origin/main
+# branch.ab +0 -0
+u UD N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path
+2 .R N... 100644 100644 100644 unused-sha unused-sha R100 new-path\told-path
+";
         let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info).has_value(GitInfo {
             branch_name: "(dfcac0b...)".to_string(),
-            stash: true,
-            unstaged: true,
+            detached: true,
+            unstaged: 1,
+            conflicted: 1,
             remote_diff: Some(GitRemoteDiff {
-                incoming: false,
-                outgoing: false,
+                upstream: "origin/main".to_string(),
+                incoming: 0,
+                outgoing: 0,
+                gone: false,
             }),
-            staged: false,
-            untracked: false,
+            ..GitInfo::default()
         });
     }
 
     #[test]
     fn includes_branch_staged_unstaged_no_remote() {
-        let status_output = "
-            # branch.oid unused-invalid-sha
-            # branch.head feature/hymenoptera
-            u .D
-            2 D.
-            1 DD
-        "
-        .trim();
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/hymenoptera
+u UD N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path
+2 R. N... 100644 100644 100644 unused-sha unused-sha R100 new-path\told-path
+1 DD N... 100644 100644 100644 unused-sha unused-sha path-not-used
+";
         let git_info = status_output.parse::<GitInfo>();
         assert_that(git_info).has_value(GitInfo {
             branch_name: "feature/hymenoptera".to_string(),
-            staged: true,
-            unstaged: true,
-            remote_diff: None,
-            stash: false,
-            untracked: false,
+            staged: 2,
+            unstaged: 1,
+            conflicted: 1,
+            ..GitInfo::default()
         });
     }
 
+    #[test]
+    fn includes_conflicted_but_not_as_staged_or_unstaged() {
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/ornithorhynchus
+u UU N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path
+u AA N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path
+";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info).has_value(GitInfo {
+            branch_name: "feature/ornithorhynchus".to_string(),
+            conflicted: 2,
+            ..GitInfo::default()
+        });
+    }
+
+    #[test]
+    fn includes_submodules_but_not_as_unstaged() {
+        let status_output = "\
+# branch.oid unused-invalid-sha
+# branch.head feature/tachyglossus
+1 .M S.M. 160000 160000 160000 unused-sha unused-sha vendored
+1 .M SC.. 160000 160000 160000 unused-sha unused-sha forked
+1 M. SC.. 160000 160000 160000 unused-sha unused-sha pinned
+1 .M N... 100644 100644 100644 unused-sha unused-sha Cargo.toml
+";
+        let git_info = status_output.parse::<GitInfo>();
+        assert_that(git_info).has_value(GitInfo {
+            branch_name: "feature/tachyglossus".to_string(),
+            submodules: 3,
+            staged: 1,
+            unstaged: 1,
+            ..GitInfo::default()
+        });
+    }
+
+    #[test]
+    fn classifies_dubious_ownership() {
+        let stderr = b"fatal: detected dubious ownership in repository at '/srv/repo'";
+        assert_that(GitFailure::classify(stderr)).has_value(GitFailure::DubiousOwnership);
+    }
+
+    #[test]
+    fn classifies_corrupt_index() {
+        let stderr = b"fatal: .git/index: index file smaller than expected";
+        assert_that(GitFailure::classify(stderr)).has_value(GitFailure::CorruptIndex);
+    }
+
+    #[test]
+    fn does_not_classify_unknown_failure() {
+        let stderr = b"fatal: not a git repository (or any of the parent directories): .git";
+        assert_that(GitFailure::classify(stderr)).is_none();
+    }
+
+    #[test]
+    fn displays_git_status_in_order() {
+        let branch = "feature/theropods";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            remote_diff: Some(GitRemoteDiff {
+                upstream: "origin/main".to_string(),
+                incoming: 0,
+                outgoing: 0,
+                gone: false,
+            }),
+            stash: 1,
+            untracked: 1,
+            staged: 1,
+            unstaged: 1,
+            conflicted: 1,
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::Rebase(None)];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.to_string()).is_equal_to(format!("{branch} BX*+$%="));
+    }
+
+    #[test]
+    fn displays_all_patches_in_order() {
+        let branch = "feature/cetacea";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            conflicted: 1,
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::Rebase(None), GitPatch::CherryPick(None)];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.to_string()).is_equal_to(format!("{branch} BHX"));
+    }
+
+    #[test]
+    fn displays_initial_after_other_states() {
+        let branch = "feature/cnidaria";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            unborn: true,
+            staged: 1,
+            sparse: true,
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.to_string()).is_equal_to(format!("{branch} S#+"));
+    }
+
+    #[test]
+    fn rewrites_branch_name_in_order() {
+        let rewrites = [
+            (
+                Regex::new("^feature/").expect("Invalid regex in git info tests"),
+                "f/",
+            ),
+            (
+                Regex::new(r"^f/(\w+-\d+)-.*").expect("Invalid regex in git info tests"),
+                "$1",
+            ),
+        ];
+        let branch_name = GitInfo::rewrite_branch_name(
+            "feature/JIRA-12345-refactor-the-billing-pipeline",
+            &rewrites,
+        );
+        assert_that(branch_name).is_equal_to("JIRA-12345");
+    }
+
+    #[test]
+    fn keeps_branch_name_without_matching_rewrite() {
+        let rewrites = [(
+            Regex::new("^feature/").expect("Invalid regex in git info tests"),
+            "f/",
+        )];
+        let branch_name = GitInfo::rewrite_branch_name("main", &rewrites);
+        assert_that(branch_name).is_equal_to("main");
+    }
+
+    #[test]
+    fn truncates_long_branch_name_at_end() {
+        let branch_name = GitInfo::truncate_branch_name(
+            "feature/refactor-billing",
+            12,
+            &config::GitBranchTruncate::End,
+            "…",
+        );
+        assert_that(branch_name).is_equal_to("feature/ref…");
+    }
+
+    #[test]
+    fn truncates_long_branch_name_in_middle() {
+        let branch_name = GitInfo::truncate_branch_name(
+            "feature/refactor-billing",
+            12,
+            &config::GitBranchTruncate::Middle,
+            "..",
+        );
+        assert_that(branch_name).is_equal_to("featu..lling");
+    }
+
+    #[test]
+    fn truncates_ellipsis_longer_than_max_length() {
+        let branch_name = GitInfo::truncate_branch_name(
+            "feature/refactor-billing",
+            2,
+            &config::GitBranchTruncate::Middle,
+            "...",
+        );
+        assert_that(branch_name).is_equal_to("..");
+    }
+
+    #[test]
+    fn does_not_truncate_branch_name_within_max_length() {
+        let branch_name = GitInfo::truncate_branch_name(
+            "feature/ünïcode",
+            15,
+            &config::GitBranchTruncate::End,
+            "…",
+        );
+        assert_that(branch_name).is_equal_to("feature/ünïcode");
+    }
+
+    #[test]
+    fn makes_age_with_largest_unit() {
+        assert_that(make_age(45)).is_equal_to("45s");
+        assert_that(make_age(12 * 60 + 59)).is_equal_to("12m");
+        assert_that(make_age(2 * 60 * 60)).is_equal_to("2h");
+        assert_that(make_age(3 * 24 * 60 * 60 + 1)).is_equal_to("3d");
+        assert_that(make_age(15 * 24 * 60 * 60)).is_equal_to("2w");
+        assert_that(make_age(800 * 24 * 60 * 60)).is_equal_to("2y");
+    }
+
+    #[test]
+    fn dirty_state_when_patch_in_progress_without_conflicts() {
+        let git_info = GitInfo {
+            branch_name: "feature/placozoa".to_string(),
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::Rebase(None)];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.state).has_value(VcsState::Dirty);
+    }
+
+    #[test]
+    fn conflict_state_when_patch_in_progress_with_conflicts() {
+        let git_info = GitInfo {
+            branch_name: "feature/placozoa".to_string(),
+            conflicted: 1,
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::Merge];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.state).has_value(VcsState::Conflict);
+    }
+
+    #[test]
+    fn clean_state_while_bisecting() {
+        let git_info = GitInfo {
+            branch_name: "feature/placozoa".to_string(),
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::Bisect];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.state).has_value(VcsState::Clean);
+    }
+
+    #[test]
+    fn dirty_state_with_untracked_files() {
+        let git_info = GitInfo {
+            branch_name: "feature/placozoa".to_string(),
+            untracked: 2,
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.state).has_value(VcsState::Dirty);
+    }
+
+    #[test]
+    fn conflict_state_with_unmerged_files_and_no_patch() {
+        let status_output = "\
+# branch.oid 26fc77b95e7e8ace0608d20a811d5b2cad292feb
+# branch.head feature/placozoa
+u UU N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path
+";
+        let git_info = status_output
+            .parse::<GitInfo>()
+            .expect("Failed to parse git status output in git info tests");
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.state).has_value(VcsState::Conflict);
+    }
+
+    #[test]
+    fn conflict_state_over_dirty_state() {
+        let git_info = GitInfo {
+            branch_name: "feature/placozoa".to_string(),
+            unstaged: 1,
+            conflicted: 1,
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.state).has_value(VcsState::Conflict);
+    }
+
+    #[test]
+    fn no_state_in_large_repo() {
+        let git_info = GitInfo {
+            branch_name: "feature/placozoa".to_string(),
+            large_repo: true,
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.state).is_none();
+    }
+
+    #[test]
+    fn marks_protected_branch() {
+        // Generated from "release/*"
+        let regexes = [Regex::new("^release/[^/]*$").expect("Invalid regex in git info tests")];
+        assert_that(GitInfo::is_protected("release/2.0", &regexes)).is_true();
+    }
+
+    #[test]
+    fn does_not_mark_nested_branch_under_protected_glob() {
+        // Generated from "release/*"
+        let regexes = [Regex::new("^release/[^/]*$").expect("Invalid regex in git info tests")];
+        assert_that(GitInfo::is_protected("release/2.0/hotfix", &regexes)).is_false();
+    }
+
+    #[test]
+    fn does_not_mark_branch_without_protected_branches() {
+        let git_info = GitInfo {
+            branch_name: "main".to_string(),
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.protected).is_false();
+    }
+
+    #[test]
+    fn displays_only_inside_git_dir_symbol_inside_git_dir() {
+        let git_info = GitInfo {
+            branch_name: "feature/sirenia".to_string(),
+            internals: Some(GitInternals::InsideGitDir),
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::Merge];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.to_string()).is_equal_to("GIT_DIR!".to_string());
+    }
+
+    #[test]
+    fn does_not_include_trailing_space_if_only_branch_name() {
+        let branch = "feature/mobula";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            ..GitInfo::default()
+        };
+        let git_patches = [];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.to_string()).is_equal_to(branch);
+    }
+
+    #[test]
+    fn skips_remote_symbols_if_no_remote() {
+        let branch = "feature/mellivora";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            stash: 1,
+            staged: 1,
+            ..GitInfo::default()
+        };
+        let git_patches = [GitPatch::CherryPick(None)];
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.to_string()).is_equal_to(format!("{branch} H+$"));
+    }
+
+    #[test]
+    fn displays_branch_stash_after_stash() {
+        let branch = "feature/mellivora";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            stash: 2,
+            branch_stash: 1,
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&[]);
+        assert_that(vcs_status.to_string()).is_equal_to(format!("{branch} $&"));
+    }
+
+    #[test]
+    fn skips_patch_symbol_if_no_patch() {
+        let git_patches = [];
+        let branch = "feature/ateles";
+        let git_info = GitInfo {
+            branch_name: branch.to_string(),
+            remote_diff: Some(GitRemoteDiff {
+                upstream: "origin/main".to_string(),
+                incoming: 0,
+                outgoing: 2,
+                gone: false,
+            }),
+            staged: 1,
+            ..GitInfo::default()
+        };
+        let vcs_status = git_info.into_vcs_status(&git_patches);
+        assert_that(vcs_status.to_string()).is_equal_to(format!("{branch} +>"));
+    }
+
     mod git_remote_diff {
         use super::*;
 
         #[test]
         fn includes_remote_on_par() {
-            let status_output = "# branch.ab +0 -0";
-            let git_remote_diff = GitRemoteDiff::parse(status_output.lines());
-            assert_that(git_remote_diff).ok().has_value(GitRemoteDiff {
-                incoming: false,
-                outgoing: false,
+            let status_output = "# branch.upstream origin/main\n# branch.ab +0 -0";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).has_value(GitRemoteDiff {
+                upstream: "origin/main".to_string(),
+                incoming: 0,
+                outgoing: 0,
+                gone: false,
             });
         }
 
         #[test]
         fn includes_remote_with_outgoing() {
-            let status_output = "# branch.ab +7 -0";
-            let git_remote_diff = GitRemoteDiff::parse(status_output.lines());
-            assert_that(git_remote_diff).ok().has_value(GitRemoteDiff {
-                outgoing: true,
-                incoming: false,
+            let status_output = "# branch.upstream origin/main\n# branch.ab +7 -0";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).has_value(GitRemoteDiff {
+                upstream: "origin/main".to_string(),
+                outgoing: 7,
+                incoming: 0,
+                gone: false,
             });
         }
 
         #[test]
         fn includes_remote_with_incoming() {
-            let status_output = "# branch.ab +0 -3";
-            let git_remote_diff = GitRemoteDiff::parse(status_output.lines());
-            assert_that(git_remote_diff).ok().has_value(GitRemoteDiff {
-                outgoing: false,
-                incoming: true,
+            let status_output = "# branch.upstream origin/main\n# branch.ab +0 -3";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).has_value(GitRemoteDiff {
+                upstream: "origin/main".to_string(),
+                outgoing: 0,
+                incoming: 3,
+                gone: false,
             });
         }
 
         #[test]
         fn includes_diverged_remote() {
-            let status_output = "# branch.ab +9 -3";
-            let git_remote_diff = GitRemoteDiff::parse(status_output.lines());
-            assert_that(git_remote_diff).ok().has_value(GitRemoteDiff {
-                outgoing: true,
-                incoming: true,
+            let status_output = "# branch.upstream origin/main\n# branch.ab +9 -3";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).has_value(GitRemoteDiff {
+                upstream: "origin/main".to_string(),
+                outgoing: 9,
+                incoming: 3,
+                gone: false,
             });
         }
 
+        #[test]
+        fn includes_gone_remote_when_no_ab_line() {
+            let status_output = "# branch.upstream fork/feature/lemur";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).has_value(GitRemoteDiff {
+                upstream: "fork/feature/lemur".to_string(),
+                incoming: 0,
+                outgoing: 0,
+                gone: true,
+            });
+        }
+
+        #[test]
+        fn none_when_no_upstream_line() {
+            let status_output = "# branch.ab +0 -0";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).is_none();
+        }
+
         #[test]
         fn none_when_no_remote_line() {
-            let status_output = "u AD";
-            let git_remote_diff = GitRemoteDiff::parse(status_output.lines());
-            assert_that(git_remote_diff).ok().is_none();
+            let status_output = "u AD N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path";
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(status_output));
+            assert_that(git_remote_diff).is_none();
         }
 
         #[test]
         fn finds_remote_line_amidst_other_output() {
             let status_lines = "
-                # stash number-not-used
+                # branch.upstream origin/main
                 # branch.ab +0 -0
-                u .D
+                u UD N... 100644 100644 100644 100644 unused-sha unused-sha unused-sha conflicted-path
             "
             .trim()
             .lines()
-            .map(str::trim);
-            let git_remote_diff = GitRemoteDiff::parse(status_lines);
-            assert_that(git_remote_diff).ok().is_some();
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n");
+            let git_remote_diff = GitRemoteDiff::from_status(&parse_status(&status_lines));
+            assert_that(git_remote_diff).is_some();
         }
 
         #[test]
-        fn displays_remote_on_par() {
+        fn maps_outgoing_and_incoming_to_ahead_and_behind() {
             let git_remote_diff = GitRemoteDiff {
-                incoming: false,
-                outgoing: false,
+                upstream: "origin/main".to_string(),
+                incoming: 5,
+                outgoing: 1,
+                gone: false,
             };
-            assert_that(git_remote_diff.to_string()).is_equal_to("=");
-        }
-
-        #[test]
-        fn displays_remote_outgoing() {
-            let git_remote_diff = GitRemoteDiff {
-                incoming: false,
-                outgoing: true,
+            let symbols = VcsDivergenceSymbols {
+                ahead: ">",
+                behind: "<",
+                on_par: "=",
+                gone: None,
             };
-            assert_that(git_remote_diff.to_string()).is_equal_to(">");
+            let vcs_divergence = git_remote_diff.into_vcs_divergence(symbols);
+            assert_that(vcs_divergence.ahead).is_equal_to(1);
+            assert_that(vcs_divergence.behind).is_equal_to(5);
         }
 
-        #[test]
-        fn displays_remote_incoming() {
-            let git_remote_diff = GitRemoteDiff {
-                incoming: true,
-                outgoing: false,
-            };
-            assert_that(git_remote_diff.to_string()).is_equal_to("<");
-        }
-
-        #[test]
-        fn displays_remote_diverged() {
-            let git_remote_diff = GitRemoteDiff {
-                incoming: true,
-                outgoing: true,
-            };
-            assert_that(git_remote_diff.to_string()).is_equal_to("<>");
+        fn parse_status(status_output: &str) -> Status {
+            status_output
+                .parse()
+                .expect("Failed to parse git status output in git remote diff tests")
         }
     }
 }
@@ -1,89 +1,282 @@
-use std::{collections::HashMap, fmt::Display, path::Path, sync::LazyLock};
+use std::{borrow::Cow, fmt::Display, fs, path::Path, sync::LazyLock};
 
-use crate::config;
+use crate::{config, git_dir::GitDir, symbol::Symbol};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum GitPatch {
     Merge,
-    Rebase,
-    CherryPick,
-    Revert,
+    Rebase(Option<GitProgress>),
+    CherryPick(Option<GitProgress>),
+    Revert(Option<GitProgress>),
+    Am(Option<GitProgress>),
+    Bisect,
 }
 
-struct GitPatchInfo {
-    detection_path: &'static str,
-    symbol: &'static str,
+// Cherry-pick and revert ranges only record the commits still to be applied,
+// so the current step and the total are unknown.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct GitProgress {
+    pub step: Option<usize>,
+    pub total: Option<usize>,
+    pub remaining: usize,
 }
 
-static GIT_PATCH_INFOS: LazyLock<HashMap<GitPatch, GitPatchInfo>> = LazyLock::new(|| {
-    let mut git_patch_infos = HashMap::new();
+type GitPatchDetector = fn(&Path) -> Option<GitPatch>;
 
-    let mut s = String::new();
+static GIT_PATCH_DETECTORS: LazyLock<Vec<GitPatchDetector>> = LazyLock::new(|| {
+    let mut git_patch_detectors: Vec<GitPatchDetector> = Vec::new();
 
-    if let Some(symbol) = config::GIT_MERGE {
-        s.push(' ');
-        git_patch_infos.insert(
-            GitPatch::Merge,
-            GitPatchInfo {
-                detection_path: "MERGE_HEAD",
-                symbol,
-            },
-        );
+    if config::GIT_MERGE.is_some() {
+        git_patch_detectors.push(|git_dir| {
+            git_dir
+                .join("MERGE_HEAD")
+                .exists()
+                .then_some(GitPatch::Merge)
+        });
     }
-    if let Some(symbol) = config::GIT_REBASE {
-        git_patch_infos.insert(
-            GitPatch::Rebase,
-            GitPatchInfo {
-                detection_path: "rebase-merge",
-                symbol,
-            },
-        );
+    if config::GIT_REBASE.is_some() {
+        git_patch_detectors.push(|git_dir| {
+            if git_dir.join("rebase-merge").exists() {
+                Some(GitPatch::Rebase(GitProgress::read(
+                    &git_dir.join("rebase-merge/msgnum"),
+                    &git_dir.join("rebase-merge/end"),
+                )))
+            } else if git_dir.join("rebase-apply/rebasing").exists() {
+                Some(GitPatch::Rebase(GitProgress::read_rebase_apply(git_dir)))
+            } else {
+                None
+            }
+        });
     }
-    if let Some(symbol) = config::GIT_CHERRY_PICK {
-        git_patch_infos.insert(
-            GitPatch::CherryPick,
-            GitPatchInfo {
-                detection_path: "CHERRY_PICK_HEAD",
-                symbol,
-            },
-        );
+    if config::GIT_CHERRY_PICK.is_some() {
+        git_patch_detectors.push(|git_dir| {
+            git_dir
+                .join("CHERRY_PICK_HEAD")
+                .exists()
+                .then(|| GitPatch::CherryPick(GitProgress::read_sequencer(git_dir)))
+        });
     }
-    if let Some(symbol) = config::GIT_REVERT {
-        git_patch_infos.insert(
-            GitPatch::Revert,
-            GitPatchInfo {
-                detection_path: "REVERT_HEAD",
-                symbol,
-            },
-        );
+    if config::GIT_REVERT.is_some() {
+        git_patch_detectors.push(|git_dir| {
+            git_dir
+                .join("REVERT_HEAD")
+                .exists()
+                .then(|| GitPatch::Revert(GitProgress::read_sequencer(git_dir)))
+        });
+    }
+    if config::GIT_AM.is_some() {
+        git_patch_detectors.push(|git_dir| {
+            git_dir
+                .join("rebase-apply/applying")
+                .exists()
+                .then(|| GitPatch::Am(GitProgress::read_rebase_apply(git_dir)))
+        });
+    }
+    if config::GIT_BISECT.is_some() {
+        git_patch_detectors.push(|git_dir| {
+            git_dir
+                .join("BISECT_LOG")
+                .exists()
+                .then_some(GitPatch::Bisect)
+        });
     }
 
-    git_patch_infos
+    git_patch_detectors
 });
 
+const STEP_PLACEHOLDER: &str = "{step}";
+const TOTAL_PLACEHOLDER: &str = "{total}";
+const REMAINING_PLACEHOLDER: &str = "{remaining}";
+
 impl GitPatch {
-    pub fn detect(current_dir: &Path) -> Option<Self> {
-        let git_dir = current_dir
-            .ancestors()
-            .map(|dir| dir.join(".git"))
-            .find(|git_dir| git_dir.exists())?;
-        GIT_PATCH_INFOS
+    // Several operations can be in progress at once, such as a cherry-pick in
+    // the middle of an interactive rebase. They are always detected in the same
+    // order: merge, rebase, cherry-pick, revert, am and bisect.
+    pub fn detect(git_dir: &GitDir) -> Vec<Self> {
+        GIT_PATCH_DETECTORS
             .iter()
-            .find_map(|(&git_patch, git_patch_info)| {
-                git_dir
-                    .join(git_patch_info.detection_path)
-                    .exists()
-                    .then_some(git_patch)
-            })
+            .filter_map(|detect| detect(git_dir.path()))
+            .collect()
     }
 }
 
 impl Display for GitPatch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let symbol = GIT_PATCH_INFOS
-            .get(self)
-            .unwrap_or_else(|| panic!("Unknown GitPatch {self:?}"))
-            .symbol;
-        write!(f, "{symbol}")
+        let (symbol, progress) = match self {
+            Self::Merge => (config::GIT_MERGE, None),
+            Self::Rebase(progress) => (config::GIT_REBASE, progress.as_ref()),
+            Self::CherryPick(progress) => (config::GIT_CHERRY_PICK, progress.as_ref()),
+            Self::Revert(progress) => (config::GIT_REVERT, progress.as_ref()),
+            Self::Am(progress) => (config::GIT_AM, progress.as_ref()),
+            Self::Bisect => (config::GIT_BISECT, None),
+        };
+        let symbol = symbol.unwrap_or_else(|| panic!("Unknown GitPatch {self:?}"));
+        write!(f, "{}", with_progress(symbol, progress))
+    }
+}
+
+fn with_progress<'a>(symbol: &'a str, progress: Option<&GitProgress>) -> Cow<'a, str> {
+    symbol.with_placeholders(&[
+        (
+            STEP_PLACEHOLDER,
+            progress.and_then(|p| p.step).map(|s| s.to_string()),
+        ),
+        (
+            TOTAL_PLACEHOLDER,
+            progress.and_then(|p| p.total).map(|t| t.to_string()),
+        ),
+        (
+            REMAINING_PLACEHOLDER,
+            progress.map(|p| p.remaining.to_string()),
+        ),
+    ])
+}
+
+impl GitProgress {
+    fn read(step_path: &Path, total_path: &Path) -> Option<Self> {
+        let step = read_number(step_path)?;
+        let total = read_number(total_path)?;
+        Some(Self {
+            step: Some(step),
+            total: Some(total),
+            remaining: (total + 1).saturating_sub(step),
+        })
+    }
+
+    fn read_rebase_apply(git_dir: &Path) -> Option<Self> {
+        Self::read(
+            &git_dir.join("rebase-apply/next"),
+            &git_dir.join("rebase-apply/last"),
+        )
+    }
+
+    fn read_sequencer(git_dir: &Path) -> Option<Self> {
+        let todo = fs::read_to_string(git_dir.join("sequencer/todo")).ok()?;
+        let remaining = todo
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .count();
+        Some(Self {
+            step: None,
+            total: None,
+            remaining,
+        })
+    }
+}
+
+fn read_number(path: &Path) -> Option<usize> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use asserting::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn detects_rebase_progress() {
+        let git_dir = mktemp();
+        write_file(&git_dir.path().join("rebase-merge/msgnum"), "3\n");
+        write_file(&git_dir.path().join("rebase-merge/end"), "7\n");
+
+        let git_patches = GitPatch::detect(&GitDir::new(git_dir.path().to_path_buf()));
+        assert_that(git_patches).contains_exactly([GitPatch::Rebase(Some(GitProgress {
+            step: Some(3),
+            total: Some(7),
+            remaining: 5,
+        }))]);
+    }
+
+    #[test]
+    fn detects_rebase_without_progress() {
+        let git_dir = mktemp();
+        create_dir(&git_dir.path().join("rebase-merge"));
+
+        let git_patches = GitPatch::detect(&GitDir::new(git_dir.path().to_path_buf()));
+        assert_that(git_patches).contains_exactly([GitPatch::Rebase(None)]);
+    }
+
+    #[test]
+    fn detects_cherry_pick_range_remaining_commits() {
+        let git_dir = mktemp();
+        write_file(&git_dir.path().join("CHERRY_PICK_HEAD"), "");
+        write_file(
+            &git_dir.path().join("sequencer/todo"),
+            "pick 9905a4f Add tail\npick 7a758b8 Add fins\n# comment\npick 26fc77b Add gills\n",
+        );
+
+        let git_patches = GitPatch::detect(&GitDir::new(git_dir.path().to_path_buf()));
+        assert_that(git_patches).contains_exactly([GitPatch::CherryPick(Some(GitProgress {
+            step: None,
+            total: None,
+            remaining: 3,
+        }))]);
+    }
+
+    #[test]
+    fn detects_all_patches_in_fixed_order() {
+        let git_dir = mktemp();
+        write_file(&git_dir.path().join("BISECT_LOG"), "");
+        write_file(&git_dir.path().join("CHERRY_PICK_HEAD"), "");
+        create_dir(&git_dir.path().join("rebase-merge"));
+        write_file(&git_dir.path().join("MERGE_HEAD"), "");
+
+        let git_patches = GitPatch::detect(&GitDir::new(git_dir.path().to_path_buf()));
+        assert_that(git_patches).contains_exactly([
+            GitPatch::Merge,
+            GitPatch::Rebase(None),
+            GitPatch::CherryPick(None),
+            GitPatch::Bisect,
+        ]);
+    }
+
+    #[test]
+    fn detects_no_patch() {
+        let git_dir = mktemp();
+        let git_patches = GitPatch::detect(&GitDir::new(git_dir.path().to_path_buf()));
+        assert_that(git_patches).is_empty();
+    }
+
+    #[test]
+    fn displays_symbol_with_progress() {
+        let progress = GitProgress {
+            step: Some(3),
+            total: Some(7),
+            remaining: 5,
+        };
+        let symbol = with_progress("B{step}/{total}", Some(&progress));
+        assert_that(symbol).is_equal_to("B3/7");
+    }
+
+    #[test]
+    fn cuts_symbol_before_unknown_progress() {
+        let progress = GitProgress {
+            step: None,
+            total: None,
+            remaining: 2,
+        };
+        let symbol = with_progress("H{remaining}:{step}/{total}", Some(&progress));
+        assert_that(symbol).is_equal_to("H2:");
+    }
+
+    fn mktemp() -> TempDir {
+        TempDir::with_prefix("zygal-git-patch-test")
+            .expect("Failed to create temporary directory in git patch tests")
+    }
+
+    fn create_dir(path: &Path) {
+        let err_msg = format!("Failed to create directory {path:?} in git patch tests");
+        fs::create_dir_all(path).expect(&err_msg);
+    }
+
+    fn write_file(path: &Path, content: &str) {
+        if let Some(parent) = path.parent() {
+            create_dir(parent);
+        }
+        let err_msg = format!("Failed to write to file {path:?} in git patch tests");
+        fs::write(path, content).expect(&err_msg);
     }
 }
@@ -0,0 +1,269 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{config, vcs::VcsStatus};
+
+#[derive(Debug, PartialEq)]
+pub struct HgInfo {
+    pub branch_name: String,
+    pub merge: bool,
+    pub rebase: bool,
+    pub histedit: bool,
+    pub dirty: usize,
+    pub added: usize,
+    pub untracked: usize,
+}
+
+impl HgInfo {
+    // Only the state files in .hg are read for the branch and in-progress
+    // operations, hg itself is only spawned for the file statuses.
+    pub fn from_hg_dir(current_dir: &Path, hg_dir: &Path) -> anyhow::Result<Self> {
+        let Some(config::Hg {
+            dirty,
+            added,
+            untracked,
+            ..
+        }) = config::HG
+        else {
+            anyhow::bail!("Mercurial information requested without an hg table");
+        };
+
+        let mut hg_info = Self {
+            branch_name: Self::read_branch_name(hg_dir),
+            merge: hg_dir.join("merge/state2").exists() || hg_dir.join("merge/state").exists(),
+            rebase: hg_dir.join("rebasestate").exists(),
+            histedit: hg_dir.join("histedit-state").exists(),
+            dirty: 0,
+            added: 0,
+            untracked: 0,
+        };
+
+        let mut status_args = vec!["status"];
+        if dirty.is_some() {
+            status_args.extend(["--modified", "--removed", "--deleted"]);
+        }
+        if added.is_some() {
+            status_args.push("--added");
+        }
+        if untracked.is_some() {
+            status_args.push("--unknown");
+        }
+        if status_args.len() == 1 {
+            return Ok(hg_info);
+        }
+
+        let output = process::Command::new("hg")
+            .args(status_args)
+            .env("HGPLAIN", "1")
+            .current_dir(current_dir)
+            .output();
+        // The branch is still worth showing when hg isn't installed
+        let Some(output) = output.ok().filter(|output| output.status.success()) else {
+            return Ok(hg_info);
+        };
+
+        // Only the status letters are read, so paths don't need to be UTF-8
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        hg_info.count_statuses(&stdout);
+        Ok(hg_info)
+    }
+
+    pub fn into_vcs_status(self) -> VcsStatus {
+        let Some(config::Hg {
+            merge,
+            rebase,
+            histedit,
+            dirty,
+            added,
+            untracked,
+        }) = config::HG
+        else {
+            return VcsStatus::new(self.branch_name);
+        };
+
+        let mut vcs_status = VcsStatus::new(self.branch_name);
+        vcs_status.push_state(merge, self.merge);
+        vcs_status.push_state(rebase, self.rebase);
+        vcs_status.push_state(histedit, self.histedit);
+        vcs_status.push_change(dirty, self.dirty);
+        vcs_status.push_change(added, self.added);
+        vcs_status.push_change(untracked, self.untracked);
+        vcs_status
+    }
+
+    // Like with git, the nearest repository wins when they are nested
+    pub fn find_hg_dir(current_dir: &Path) -> Option<PathBuf> {
+        current_dir
+            .ancestors()
+            .find(|dir| {
+                [".hg", ".git", ".jj"]
+                    .iter()
+                    .any(|vcs_dir| dir.join(vcs_dir).exists())
+            })
+            .map(|dir| dir.join(".hg"))
+            .filter(|hg_dir| hg_dir.is_dir())
+    }
+
+    // The active bookmark is more specific than the branch, which is
+    // "default" when .hg/branch is missing.
+    fn read_branch_name(hg_dir: &Path) -> String {
+        ["bookmarks.current", "branch"]
+            .iter()
+            .filter_map(|file_name| fs::read_to_string(hg_dir.join(file_name)).ok())
+            .map(|name| name.trim().to_string())
+            .find(|name| !name.is_empty())
+            .unwrap_or_else(|| "default".to_string())
+    }
+
+    fn count_statuses(&mut self, hg_status_output: &str) {
+        for line in hg_status_output.lines() {
+            match line.chars().next() {
+                Some('M' | 'R' | '!') => self.dirty += 1,
+                Some('A') => self.added += 1,
+                Some('?') => self.untracked += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use asserting::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn uses_active_bookmark_over_branch() {
+        let tmp_dir = mktemp();
+        write_file(&tmp_dir.path().join("branch"), "stable\n");
+        write_file(&tmp_dir.path().join("bookmarks.current"), "feature/x");
+
+        let branch_name = HgInfo::read_branch_name(tmp_dir.path());
+        assert_that(branch_name).is_equal_to("feature/x");
+    }
+
+    #[test]
+    fn uses_branch_without_active_bookmark() {
+        let tmp_dir = mktemp();
+        write_file(&tmp_dir.path().join("branch"), "stable\n");
+
+        let branch_name = HgInfo::read_branch_name(tmp_dir.path());
+        assert_that(branch_name).is_equal_to("stable");
+    }
+
+    #[test]
+    fn uses_default_branch_without_branch_file() {
+        let tmp_dir = mktemp();
+
+        let branch_name = HgInfo::read_branch_name(tmp_dir.path());
+        assert_that(branch_name).is_equal_to("default");
+    }
+
+    #[test]
+    fn detects_in_progress_operations() {
+        let tmp_dir = mktemp();
+        let hg_dir = tmp_dir.path().join(".hg");
+        fs::create_dir_all(hg_dir.join("merge")).expect("Failed to create .hg in hg info tests");
+        write_file(&hg_dir.join("merge/state2"), "");
+        write_file(&hg_dir.join("histedit-state"), "");
+
+        let hg_info = HgInfo::from_hg_dir(tmp_dir.path(), &hg_dir)
+            .expect("Failed to read .hg in hg info tests");
+        assert_that(hg_info.merge).is_true();
+        assert_that(hg_info.rebase).is_false();
+        assert_that(hg_info.histedit).is_true();
+    }
+
+    #[test]
+    fn counts_statuses() {
+        let mut hg_info = HgInfo {
+            branch_name: "default".to_string(),
+            merge: false,
+            rebase: false,
+            histedit: false,
+            dirty: 0,
+            added: 0,
+            untracked: 0,
+        };
+        hg_info.count_statuses("M src/lib.rs\nA src/hg.rs\nR old.rs\n! gone.rs\n? notes.txt\n");
+        assert_that(hg_info).is_equal_to(HgInfo {
+            branch_name: "default".to_string(),
+            merge: false,
+            rebase: false,
+            histedit: false,
+            dirty: 3,
+            added: 1,
+            untracked: 1,
+        });
+    }
+
+    #[test]
+    fn displays_branch_and_symbols_in_order() {
+        let hg_info = HgInfo {
+            branch_name: "stable".to_string(),
+            merge: true,
+            rebase: false,
+            histedit: true,
+            dirty: 2,
+            added: 1,
+            untracked: 3,
+        };
+        let vcs_status = hg_info.into_vcs_status();
+        assert_that(vcs_status.to_string()).is_equal_to("stable MH*+%");
+    }
+
+    #[test]
+    fn displays_only_branch_when_clean() {
+        let hg_info = HgInfo {
+            branch_name: "default".to_string(),
+            merge: false,
+            rebase: false,
+            histedit: false,
+            dirty: 0,
+            added: 0,
+            untracked: 0,
+        };
+        let vcs_status = hg_info.into_vcs_status();
+        assert_that(vcs_status.to_string()).is_equal_to("default");
+    }
+
+    #[test]
+    fn finds_nearest_hg_dir() {
+        let tmp_dir = mktemp();
+        let current_dir = tmp_dir.path().join("src");
+        fs::create_dir_all(tmp_dir.path().join(".hg"))
+            .expect("Failed to create .hg in hg info tests");
+        fs::create_dir_all(&current_dir).expect("Failed to create src in hg info tests");
+
+        let hg_dir = HgInfo::find_hg_dir(&current_dir);
+        assert_that(hg_dir).has_value(tmp_dir.path().join(".hg"));
+    }
+
+    #[test]
+    fn no_hg_dir_when_git_is_nearer() {
+        let tmp_dir = mktemp();
+        let current_dir = tmp_dir.path().join("vendored");
+        fs::create_dir_all(tmp_dir.path().join(".hg"))
+            .expect("Failed to create .hg in hg info tests");
+        fs::create_dir_all(current_dir.join(".git"))
+            .expect("Failed to create .git in hg info tests");
+
+        let hg_dir = HgInfo::find_hg_dir(&current_dir);
+        assert_that(hg_dir).is_none();
+    }
+
+    fn mktemp() -> TempDir {
+        TempDir::with_prefix("zygal-hg-info-test")
+            .expect("Failed to create temporary directory in hg info tests")
+    }
+
+    fn write_file(path: &Path, content: &str) {
+        let err_msg = format!("Failed to write to file {path:?} in hg info tests");
+        fs::write(path, content).expect(&err_msg);
+    }
+}
@@ -0,0 +1,220 @@
+use std::{path::Path, process, str::FromStr};
+
+use anyhow::Context;
+
+use crate::{
+    config,
+    vcs::{VcsBookmarks, VcsStatus},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct JjInfo {
+    pub change_id: String,
+    pub bookmarks: Vec<String>,
+    pub conflict: bool,
+    pub empty: bool,
+}
+
+// One value per line, so that bookmark names can't be confused with the rest
+const JJ_LOG_TEMPLATE: &str = r#"change_id.shortest(8) ++ "\n"
+    ++ local_bookmarks.map(|b| b.name()).join(",") ++ "\n"
+    ++ if(conflict, "true", "false") ++ "\n"
+    ++ if(empty, "true", "false") ++ "\n""#;
+
+impl FromStr for JjInfo {
+    type Err = anyhow::Error;
+
+    fn from_str(jj_log_output: &str) -> anyhow::Result<Self> {
+        let mut lines = jj_log_output.lines().map(str::trim);
+
+        let change_id = lines
+            .next()
+            .filter(|change_id| !change_id.is_empty())
+            .context("Missing change id in jj log output")?
+            .to_string();
+        let bookmarks = lines
+            .next()
+            .context("Missing bookmarks in jj log output")?
+            .split(',')
+            .filter(|bookmark| !bookmark.is_empty())
+            .map(str::to_string)
+            .collect();
+        let conflict = lines.next().context("Missing conflict in jj log output")? == "true";
+        let empty = lines.next().context("Missing empty in jj log output")? == "true";
+
+        Ok(Self {
+            change_id,
+            bookmarks,
+            conflict,
+            empty,
+        })
+    }
+}
+
+impl JjInfo {
+    pub fn from_jj_log_output(current_dir: &Path) -> anyhow::Result<Option<Self>> {
+        // Snapshotting the working copy would create an operation on every
+        // prompt, racing with the user's own jj commands.
+        let Ok(output) = process::Command::new("jj")
+            .args([
+                "log",
+                "--ignore-working-copy",
+                "--at-op=@",
+                "--no-graph",
+                "--color=never",
+                "--revisions=@",
+                "--template",
+                JJ_LOG_TEMPLATE,
+            ])
+            .current_dir(current_dir)
+            .output()
+        else {
+            // jj isn't installed, which is fine in colocated repositories
+            return Ok(None);
+        };
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let stdout = String::from_utf8(output.stdout)
+            .context("Failed to read jj log output as an UTF-8 string")?;
+        stdout.parse().map(Some)
+    }
+
+    pub fn into_vcs_status(self) -> VcsStatus {
+        let Some(config::Jj {
+            bookmarks,
+            conflict,
+            empty,
+        }) = config::JJ
+        else {
+            return VcsStatus::new(self.change_id);
+        };
+
+        let mut vcs_status = VcsStatus::new(self.change_id);
+        vcs_status.bookmarks = bookmarks.map(|symbol| VcsBookmarks {
+            symbol,
+            names: self.bookmarks,
+        });
+        vcs_status.push_state(conflict, self.conflict);
+        vcs_status.push_state(empty, self.empty);
+        vcs_status
+    }
+
+    // Colocated repositories have both .jj and .git in the same directory, in
+    // which case jj wins since it keeps HEAD detached.
+    pub fn is_nearest_vcs(current_dir: &Path) -> bool {
+        current_dir
+            .ancestors()
+            .find(|dir| {
+                [".jj", ".git", ".hg"]
+                    .iter()
+                    .any(|vcs_dir| dir.join(vcs_dir).exists())
+            })
+            .is_some_and(|dir| dir.join(".jj").is_dir())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    use asserting::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_change_id_bookmarks_and_states() {
+        let jj_log_output = "kmtzqqwl\nmain,feature/x\nfalse\ntrue\n";
+        let jj_info = jj_log_output.parse::<JjInfo>();
+        assert_that(jj_info).has_value(JjInfo {
+            change_id: "kmtzqqwl".to_string(),
+            bookmarks: vec!["main".to_string(), "feature/x".to_string()],
+            conflict: false,
+            empty: true,
+        });
+    }
+
+    #[test]
+    fn parses_no_bookmarks() {
+        let jj_log_output = "zy\n\ntrue\nfalse\n";
+        let jj_info = jj_log_output.parse::<JjInfo>();
+        assert_that(jj_info).has_value(JjInfo {
+            change_id: "zy".to_string(),
+            bookmarks: vec![],
+            conflict: true,
+            empty: false,
+        });
+    }
+
+    #[test]
+    fn error_when_missing_lines() {
+        let jj_info = "kmtzqqwl\nmain\n".parse::<JjInfo>();
+        assert_that(jj_info).is_err();
+    }
+
+    #[test]
+    fn displays_change_id_bookmarks_and_states() {
+        let jj_info = JjInfo {
+            change_id: "kmtzqqwl".to_string(),
+            bookmarks: vec!["main".to_string(), "feature/sauria".to_string()],
+            conflict: true,
+            empty: true,
+        };
+        let vcs_status = jj_info.into_vcs_status();
+        assert_that(vcs_status.to_string()).is_equal_to("kmtzqqwl[main,feature/sauria] XE");
+    }
+
+    #[test]
+    fn displays_only_change_id_without_bookmarks_or_states() {
+        let jj_info = JjInfo {
+            change_id: "kmtzqqwl".to_string(),
+            bookmarks: vec![],
+            conflict: false,
+            empty: false,
+        };
+        let vcs_status = jj_info.into_vcs_status();
+        assert_that(vcs_status.to_string()).is_equal_to("kmtzqqwl");
+    }
+
+    #[test]
+    fn jj_is_nearest_in_colocated_repository() {
+        let tmp_dir = mktemp();
+        let current_dir = tmp_dir.path().join("src");
+        create_dir(&tmp_dir.path().join(".jj"));
+        create_dir(&tmp_dir.path().join(".git"));
+        create_dir(&current_dir);
+
+        assert_that(JjInfo::is_nearest_vcs(&current_dir)).is_true();
+    }
+
+    #[test]
+    fn jj_is_not_nearest_in_nested_git_repository() {
+        let tmp_dir = mktemp();
+        let current_dir = tmp_dir.path().join("vendored");
+        create_dir(&tmp_dir.path().join(".jj"));
+        create_dir(&current_dir.join(".git"));
+
+        assert_that(JjInfo::is_nearest_vcs(&current_dir)).is_false();
+    }
+
+    #[test]
+    fn jj_is_not_nearest_without_jj_directory() {
+        let tmp_dir = mktemp();
+        create_dir(&tmp_dir.path().join(".git"));
+
+        assert_that(JjInfo::is_nearest_vcs(tmp_dir.path())).is_false();
+    }
+
+    fn mktemp() -> TempDir {
+        TempDir::with_prefix("zygal-jj-info-test")
+            .expect("Failed to create temporary directory in jj info tests")
+    }
+
+    fn create_dir(path: &Path) {
+        let err_msg = format!("Failed to create directory {path:?} in jj info tests");
+        fs::create_dir_all(path).expect(&err_msg);
+    }
+}
@@ -1,6 +1,12 @@
+mod git_dir;
 mod git_info;
 mod git_patch;
+mod hg_info;
+mod jj_info;
+pub mod porcelain;
 mod prompt;
+mod symbol;
+mod vcs;
 
 include!(env!("CONFIG_IN"));
 
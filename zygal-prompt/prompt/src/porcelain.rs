@@ -0,0 +1,945 @@
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+use anyhow::{Context, bail};
+
+// Typed `git status --porcelain=v2 --branch` output, either
+// separated by new lines or by NUL characters with -z. Paths quoted by git are
+// unquoted.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Status {
+    pub oid: Option<Oid>,
+    pub head: Option<Head>,
+    pub upstream: Option<String>,
+    pub ahead_behind: Option<AheadBehind>,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Oid {
+    // No commit yet, as in freshly initialized repositories
+    Initial,
+    Commit(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Head {
+    Branch(String),
+    Detached,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AheadBehind {
+    pub ahead: u64,
+    pub behind: u64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Entry {
+    Ordinary(Changed),
+    RenamedOrCopied(Changed, Rename),
+    Unmerged(Unmerged),
+    Untracked(String),
+    Ignored(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Changed {
+    pub xy: Xy,
+    pub submodule: Option<SubmoduleState>,
+    pub head_mode: u32,
+    pub index_mode: u32,
+    pub worktree_mode: u32,
+    pub head_oid: String,
+    pub index_oid: String,
+    pub path: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rename {
+    pub kind: RenameKind,
+    pub score: u8,
+    pub original_path: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RenameKind {
+    Renamed,
+    Copied,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Unmerged {
+    pub xy: Xy,
+    pub submodule: Option<SubmoduleState>,
+    pub stage_modes: [u32; 3],
+    pub worktree_mode: u32,
+    pub stage_oids: [String; 3],
+    pub path: String,
+}
+
+// X is the state of the index compared to HEAD, Y the state of the worktree
+// compared to the index.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Xy {
+    pub index: FileState,
+    pub worktree: FileState,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FileState {
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    UpdatedButUnmerged,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SubmoduleState {
+    pub commit_changed: bool,
+    pub tracked_changes: bool,
+    pub untracked_changes: bool,
+}
+
+impl Entry {
+    // Ordinary, renamed and copied entries, the ones with a HEAD and index side
+    pub fn changed(&self) -> Option<&Changed> {
+        match self {
+            Self::Ordinary(changed) | Self::RenamedOrCopied(changed, _) => Some(changed),
+            Self::Unmerged(_) | Self::Untracked(_) | Self::Ignored(_) => None,
+        }
+    }
+
+    pub fn xy(&self) -> Option<Xy> {
+        match self {
+            Self::Ordinary(changed) | Self::RenamedOrCopied(changed, _) => Some(changed.xy),
+            Self::Unmerged(unmerged) => Some(unmerged.xy),
+            Self::Untracked(_) | Self::Ignored(_) => None,
+        }
+    }
+
+    pub fn submodule(&self) -> Option<SubmoduleState> {
+        match self {
+            Self::Ordinary(changed) | Self::RenamedOrCopied(changed, _) => changed.submodule,
+            Self::Unmerged(unmerged) => unmerged.submodule,
+            Self::Untracked(_) | Self::Ignored(_) => None,
+        }
+    }
+}
+
+impl SubmoduleState {
+    pub fn is_changed(&self) -> bool {
+        self.commit_changed || self.tracked_changes || self.untracked_changes
+    }
+}
+
+// Without -z, git quotes the paths that contain unusual characters, so that
+// every entry fits on its own line.
+impl FromStr for Status {
+    type Err = anyhow::Error;
+
+    fn from_str(git_status_output: &str) -> anyhow::Result<Self> {
+        let mut status = Self::default();
+        for line in git_status_output.split('\n').filter(|l| !l.is_empty()) {
+            if let Some(header) = line.strip_prefix("# ") {
+                status.parse_header(header)?;
+            } else {
+                status.push_entry(Entry::parse(line, Paths::Quoted));
+            }
+        }
+        Ok(status)
+    }
+}
+
+impl Status {
+    // With -z, paths are never quoted, and the original path of renamed and
+    // copied entries is in the record following them.
+    pub fn from_nul_separated(git_status_output: &str) -> anyhow::Result<Self> {
+        let mut status = Self::default();
+        let mut records = git_status_output.split('\0').filter(|r| !r.is_empty());
+        while let Some(record) = records.next() {
+            if let Some(header) = record.strip_prefix("# ") {
+                status.parse_header(header)?;
+                continue;
+            }
+            // Taken even when the entry is malformed, not to be mistaken for an
+            // entry itself
+            let original_path = if record.starts_with("2 ") {
+                records.next()
+            } else {
+                None
+            };
+            status.push_entry(Entry::parse(record, Paths::Raw { original_path }));
+        }
+        Ok(status)
+    }
+
+    // Entries that can't be parsed, such as ones added in future git versions,
+    // are skipped rather than failing the whole status.
+    fn push_entry(&mut self, entry: anyhow::Result<Entry>) {
+        if let Ok(entry) = entry {
+            self.entries.push(entry);
+        }
+    }
+
+    fn parse_header(&mut self, header: &str) -> anyhow::Result<()> {
+        // Unknown headers are skipped, since git may add new ones
+        let Some((key, value)) = header.split_once(' ') else {
+            return Ok(());
+        };
+
+        match key {
+            "branch.oid" => {
+                self.oid = Some(match value {
+                    "(initial)" => Oid::Initial,
+                    oid => Oid::Commit(oid.to_string()),
+                })
+            }
+            "branch.head" => {
+                self.head = Some(match value {
+                    "(detached)" => Head::Detached,
+                    branch => Head::Branch(branch.to_string()),
+                })
+            }
+            "branch.upstream" => self.upstream = Some(value.to_string()),
+            "branch.ab" => {
+                let (ahead, behind) = value
+                    .split_once(' ')
+                    .context("Malformed # branch.ab header in git status output")?;
+                let ahead = ahead
+                    .strip_prefix('+')
+                    .context("Missing + in ahead count of # branch.ab header")?
+                    .parse()
+                    .context("Failed to parse ahead count in # branch.ab header")?;
+                let behind = behind
+                    .strip_prefix('-')
+                    .context("Missing - in behind count of # branch.ab header")?
+                    .parse()
+                    .context("Failed to parse behind count in # branch.ab header")?;
+                self.ahead_behind = Some(AheadBehind { ahead, behind });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// Where the paths of an entry come from, and whether they are quoted
+enum Paths<'a> {
+    Quoted,
+    Raw { original_path: Option<&'a str> },
+}
+
+impl FromStr for Entry {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> anyhow::Result<Self> {
+        Self::parse(line, Paths::Quoted)
+    }
+}
+
+impl Entry {
+    fn parse(record: &str, paths: Paths) -> anyhow::Result<Self> {
+        let Some((kind, fields)) = record.split_once(' ') else {
+            bail!("Missing entry fields in {record}");
+        };
+        let parse_path = |path: &str| match paths {
+            Paths::Quoted => unquote_path(path),
+            Paths::Raw { .. } => Ok(path.to_string()),
+        };
+
+        match kind {
+            "1" => {
+                let fields = split_fields::<8>(fields)?;
+                let path = parse_path(fields[7])?;
+                Ok(Self::Ordinary(Changed::from_fields(&fields, path)?))
+            }
+            "2" => {
+                let fields = split_fields::<9>(fields)?;
+                let (path, original_path) = match paths {
+                    Paths::Quoted => fields[8]
+                        .split_once('\t')
+                        .context("Missing original path of renamed or copied entry")?,
+                    Paths::Raw { original_path } => (
+                        fields[8],
+                        original_path
+                            .context("Missing original path of renamed or copied entry")?,
+                    ),
+                };
+                let (kind, score) = match fields[7].split_at_checked(1) {
+                    Some(("R", score)) => (RenameKind::Renamed, score),
+                    Some(("C", score)) => (RenameKind::Copied, score),
+                    _ => bail!("Invalid rename or copy score {}", fields[7]),
+                };
+                let rename = Rename {
+                    kind,
+                    score: score.parse().context("Failed to parse rename score")?,
+                    original_path: parse_path(original_path)?,
+                };
+                Ok(Self::RenamedOrCopied(
+                    Changed::from_fields(&fields, parse_path(path)?)?,
+                    rename,
+                ))
+            }
+            "u" => {
+                let fields = split_fields::<10>(fields)?;
+                Ok(Self::Unmerged(Unmerged {
+                    xy: fields[0].parse()?,
+                    submodule: parse_submodule(fields[1])?,
+                    stage_modes: [
+                        parse_mode(fields[2])?,
+                        parse_mode(fields[3])?,
+                        parse_mode(fields[4])?,
+                    ],
+                    worktree_mode: parse_mode(fields[5])?,
+                    stage_oids: [
+                        fields[6].to_string(),
+                        fields[7].to_string(),
+                        fields[8].to_string(),
+                    ],
+                    path: parse_path(fields[9])?,
+                }))
+            }
+            "?" => Ok(Self::Untracked(parse_path(fields)?)),
+            "!" => Ok(Self::Ignored(parse_path(fields)?)),
+            _ => bail!("Unknown entry kind {kind}"),
+        }
+    }
+}
+
+impl Changed {
+    fn from_fields(fields: &[&str], path: String) -> anyhow::Result<Self> {
+        Ok(Self {
+            xy: fields[0].parse()?,
+            submodule: parse_submodule(fields[1])?,
+            head_mode: parse_mode(fields[2])?,
+            index_mode: parse_mode(fields[3])?,
+            worktree_mode: parse_mode(fields[4])?,
+            head_oid: fields[5].to_string(),
+            index_oid: fields[6].to_string(),
+            path,
+        })
+    }
+}
+
+impl FromStr for Xy {
+    type Err = anyhow::Error;
+
+    fn from_str(xy: &str) -> anyhow::Result<Self> {
+        let mut states = xy.chars().map(FileState::try_from);
+        match (states.next(), states.next(), states.next()) {
+            (Some(index), Some(worktree), None) => Ok(Self {
+                index: index?,
+                worktree: worktree?,
+            }),
+            _ => bail!("Invalid XY field {xy}"),
+        }
+    }
+}
+
+impl TryFrom<char> for FileState {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> anyhow::Result<Self> {
+        Ok(match c {
+            '.' => Self::Unmodified,
+            'M' => Self::Modified,
+            'T' => Self::TypeChanged,
+            'A' => Self::Added,
+            'D' => Self::Deleted,
+            'R' => Self::Renamed,
+            'C' => Self::Copied,
+            'U' => Self::UpdatedButUnmerged,
+            _ => bail!("Invalid file state {c}"),
+        })
+    }
+}
+
+// The last field is the path, which can contain spaces
+fn split_fields<const N: usize>(fields: &str) -> anyhow::Result<Vec<&str>> {
+    let fields: Vec<&str> = fields.splitn(N, ' ').collect();
+    if fields.len() != N {
+        bail!("Expected {N} fields, found {}", fields.len());
+    }
+    Ok(fields)
+}
+
+fn parse_mode(mode: &str) -> anyhow::Result<u32> {
+    u32::from_str_radix(mode, 8).with_context(|| format!("Invalid file mode {mode}"))
+}
+
+fn parse_submodule(submodule: &str) -> anyhow::Result<Option<SubmoduleState>> {
+    let flag = |c: Option<char>, set: char| match c {
+        Some(c) if c == set => Ok(true),
+        Some('.') => Ok(false),
+        _ => bail!("Invalid submodule field {submodule}"),
+    };
+
+    let mut chars = submodule.chars();
+    match chars.next() {
+        Some('N') if chars.as_str() == "..." => Ok(None),
+        Some('S') => {
+            let state = SubmoduleState {
+                commit_changed: flag(chars.next(), 'C')?,
+                tracked_changes: flag(chars.next(), 'M')?,
+                untracked_changes: flag(chars.next(), 'U')?,
+            };
+            if chars.next().is_some() {
+                bail!("Invalid submodule field {submodule}");
+            }
+            Ok(Some(state))
+        }
+        _ => bail!("Invalid submodule field {submodule}"),
+    }
+}
+
+// Paths with control characters, double quotes or backslashes are quoted like C
+// strings, with octal escapes for the other control characters. git also
+// escapes non-ASCII bytes unless core.quotePath is false.
+fn unquote_path(path: &str) -> anyhow::Result<String> {
+    let Some(quoted) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return Ok(path.to_string());
+    };
+
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        bytes.push(match chars.next() {
+            Some('a') => b'\x07',
+            Some('b') => b'\x08',
+            Some('t') => b'\t',
+            Some('n') => b'\n',
+            Some('v') => b'\x0b',
+            Some('f') => b'\x0c',
+            Some('r') => b'\r',
+            Some('"') => b'"',
+            Some('\\') => b'\\',
+            Some(first @ '0'..='3') => {
+                let digits: String = [Some(first), chars.next(), chars.next()]
+                    .into_iter()
+                    .collect::<Option<_>>()
+                    .with_context(|| format!("Truncated octal escape in path {path}"))?;
+                u8::from_str_radix(&digits, 8)
+                    .with_context(|| format!("Invalid octal escape in path {path}"))?
+            }
+            _ => bail!("Invalid escape sequence in path {path}"),
+        });
+    }
+    String::from_utf8(bytes).with_context(|| format!("Path {path} is not valid UTF-8"))
+}
+
+fn quote_path(path: &str) -> Cow<'_, str> {
+    let needs_quoting = |c: char| c.is_ascii_control() || c == '"' || c == '\\';
+    if !path.contains(needs_quoting) {
+        return Cow::Borrowed(path);
+    }
+
+    let mut quoted = String::from('"');
+    for c in path.chars() {
+        match c {
+            '\x07' => quoted.push_str("\\a"),
+            '\x08' => quoted.push_str("\\b"),
+            '\t' => quoted.push_str("\\t"),
+            '\n' => quoted.push_str("\\n"),
+            '\x0b' => quoted.push_str("\\v"),
+            '\x0c' => quoted.push_str("\\f"),
+            '\r' => quoted.push_str("\\r"),
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\{:03o}", c as u8)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+// Writes the output back in the format git prints it without -z
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.oid {
+            Some(Oid::Initial) => writeln!(f, "# branch.oid (initial)")?,
+            Some(Oid::Commit(oid)) => writeln!(f, "# branch.oid {oid}")?,
+            None => {}
+        }
+        match &self.head {
+            Some(Head::Detached) => writeln!(f, "# branch.head (detached)")?,
+            Some(Head::Branch(branch)) => writeln!(f, "# branch.head {branch}")?,
+            None => {}
+        }
+        if let Some(upstream) = &self.upstream {
+            writeln!(f, "# branch.upstream {upstream}")?;
+        }
+        if let Some(AheadBehind { ahead, behind }) = &self.ahead_behind {
+            writeln!(f, "# branch.ab +{ahead} -{behind}")?;
+        }
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ordinary(changed) => write!(f, "1 {changed} {}", quote_path(&changed.path)),
+            Self::RenamedOrCopied(changed, rename) => {
+                let kind = match rename.kind {
+                    RenameKind::Renamed => 'R',
+                    RenameKind::Copied => 'C',
+                };
+                write!(
+                    f,
+                    "2 {changed} {kind}{} {}\t{}",
+                    rename.score,
+                    quote_path(&changed.path),
+                    quote_path(&rename.original_path)
+                )
+            }
+            Self::Unmerged(unmerged) => {
+                let [m1, m2, m3] = unmerged.stage_modes;
+                let [h1, h2, h3] = &unmerged.stage_oids;
+                write!(
+                    f,
+                    "u {} {} {m1:06o} {m2:06o} {m3:06o} {:06o} {h1} {h2} {h3} {}",
+                    unmerged.xy,
+                    SubmoduleField(unmerged.submodule),
+                    unmerged.worktree_mode,
+                    quote_path(&unmerged.path)
+                )
+            }
+            Self::Untracked(path) => write!(f, "? {}", quote_path(path)),
+            Self::Ignored(path) => write!(f, "! {}", quote_path(path)),
+        }
+    }
+}
+
+// Everything but the path, which is placed differently for renames
+impl Display for Changed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {:06o} {:06o} {:06o} {} {}",
+            self.xy,
+            SubmoduleField(self.submodule),
+            self.head_mode,
+            self.index_mode,
+            self.worktree_mode,
+            self.head_oid,
+            self.index_oid
+        )
+    }
+}
+
+impl Display for Xy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.index, self.worktree)
+    }
+}
+
+impl Display for FileState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Self::Unmodified => '.',
+            Self::Modified => 'M',
+            Self::TypeChanged => 'T',
+            Self::Added => 'A',
+            Self::Deleted => 'D',
+            Self::Renamed => 'R',
+            Self::Copied => 'C',
+            Self::UpdatedButUnmerged => 'U',
+        };
+        write!(f, "{c}")
+    }
+}
+
+struct SubmoduleField(Option<SubmoduleState>);
+
+impl Display for SubmoduleField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(state) = self.0 else {
+            return write!(f, "N...");
+        };
+        let flag = |set: bool, c: char| if set { c } else { '.' };
+        write!(
+            f,
+            "S{}{}{}",
+            flag(state.commit_changed, 'C'),
+            flag(state.tracked_changes, 'M'),
+            flag(state.untracked_changes, 'U')
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use asserting::prelude::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parses_headers() {
+        let status_output = "\
+# branch.oid 26fc77b95e7e8ace0608d20a811d5b2cad292feb
+# branch.head feature/x
+# branch.upstream origin/feature/x
+# branch.ab +2 -5
+";
+        let status = status_output.parse::<Status>();
+        assert_that(status).has_value(Status {
+            oid: Some(Oid::Commit(
+                "26fc77b95e7e8ace0608d20a811d5b2cad292feb".to_string(),
+            )),
+            head: Some(Head::Branch("feature/x".to_string())),
+            upstream: Some("origin/feature/x".to_string()),
+            ahead_behind: Some(AheadBehind {
+                ahead: 2,
+                behind: 5,
+            }),
+            entries: vec![],
+        });
+    }
+
+    #[test]
+    fn parses_initial_oid_and_detached_head() {
+        let status = "# branch.oid (initial)\n# branch.head (detached)\n".parse::<Status>();
+        assert_that(status).has_value(Status {
+            oid: Some(Oid::Initial),
+            head: Some(Head::Detached),
+            ..Status::default()
+        });
+    }
+
+    #[test]
+    fn parses_renamed_entry_with_spaces_in_paths() {
+        let entry = "2 R. N... 100644 100644 100644 1f2e 1f2e R87 new name.rs\told name.rs"
+            .parse::<Entry>();
+        assert_that(entry).has_value(Entry::RenamedOrCopied(
+            Changed {
+                xy: Xy {
+                    index: FileState::Renamed,
+                    worktree: FileState::Unmodified,
+                },
+                submodule: None,
+                head_mode: 0o100644,
+                index_mode: 0o100644,
+                worktree_mode: 0o100644,
+                head_oid: "1f2e".to_string(),
+                index_oid: "1f2e".to_string(),
+                path: "new name.rs".to_string(),
+            },
+            Rename {
+                kind: RenameKind::Renamed,
+                score: 87,
+                original_path: "old name.rs".to_string(),
+            },
+        ));
+    }
+
+    #[test]
+    fn parses_unmerged_submodule_entry() {
+        let entry = "u UU SCM. 160000 160000 160000 160000 a1 b2 c3 vendored".parse::<Entry>();
+        assert_that(entry).has_value(Entry::Unmerged(Unmerged {
+            xy: Xy {
+                index: FileState::UpdatedButUnmerged,
+                worktree: FileState::UpdatedButUnmerged,
+            },
+            submodule: Some(SubmoduleState {
+                commit_changed: true,
+                tracked_changes: true,
+                untracked_changes: false,
+            }),
+            stage_modes: [0o160000; 3],
+            worktree_mode: 0o160000,
+            stage_oids: ["a1".to_string(), "b2".to_string(), "c3".to_string()],
+            path: "vendored".to_string(),
+        }));
+    }
+
+    #[test]
+    fn parses_whitespace_only_path() {
+        let status = "# branch.head main\n?  \n".parse::<Status>();
+        assert_that(status)
+            .ok()
+            .mapping(|s| s.entries)
+            .contains_exactly([Entry::Untracked(" ".to_string())]);
+    }
+
+    #[test]
+    fn unquotes_quoted_paths() {
+        let status = r#"? "tab\there \"quoted\" back\\slash"
+? "caf\303\251"
+"#
+        .parse::<Status>();
+        assert_that(status)
+            .ok()
+            .mapping(|s| s.entries)
+            .contains_exactly([
+                Entry::Untracked("tab\there \"quoted\" back\\slash".to_string()),
+                Entry::Untracked("café".to_string()),
+            ]);
+    }
+
+    #[test]
+    fn skips_malformed_entries() {
+        let status_output = "\
+# branch.head main
+3 M. N... whatever
+? \"invalid\\q\"
+? kept
+";
+        let status = status_output.parse::<Status>();
+        assert_that(status)
+            .ok()
+            .mapping(|s| s.entries)
+            .contains_exactly([Entry::Untracked("kept".to_string())]);
+    }
+
+    #[test]
+    fn parses_nul_separated_output() {
+        let status_output = "\
+# branch.oid (initial)\0\
+# branch.head main\0\
+2 R. N... 100644 100644 100644 1f2e 1f2e R100 new\tname\0old\nname\0\
+?  \0\
+? \"raw\"\0";
+        let status = Status::from_nul_separated(status_output);
+        assert_that(status).has_value(Status {
+            oid: Some(Oid::Initial),
+            head: Some(Head::Branch("main".to_string())),
+            entries: vec![
+                Entry::RenamedOrCopied(
+                    Changed {
+                        xy: Xy {
+                            index: FileState::Renamed,
+                            worktree: FileState::Unmodified,
+                        },
+                        submodule: None,
+                        head_mode: 0o100644,
+                        index_mode: 0o100644,
+                        worktree_mode: 0o100644,
+                        head_oid: "1f2e".to_string(),
+                        index_oid: "1f2e".to_string(),
+                        path: "new\tname".to_string(),
+                    },
+                    Rename {
+                        kind: RenameKind::Renamed,
+                        score: 100,
+                        original_path: "old\nname".to_string(),
+                    },
+                ),
+                Entry::Untracked(" ".to_string()),
+                Entry::Untracked("\"raw\"".to_string()),
+            ],
+            ..Status::default()
+        });
+    }
+
+    #[test]
+    fn skips_original_path_of_malformed_nul_separated_rename() {
+        let status_output = "2 R. N... 100644 bad\0? looks-like-entry\0? kept\0";
+        let status = Status::from_nul_separated(status_output);
+        assert_that(status)
+            .ok()
+            .mapping(|s| s.entries)
+            .contains_exactly([Entry::Untracked("kept".to_string())]);
+    }
+
+    #[test]
+    fn error_when_unknown_entry_kind() {
+        assert_that("3 M. N... whatever".parse::<Entry>()).is_err();
+    }
+
+    #[test]
+    fn error_when_missing_fields() {
+        assert_that("1 M. N... 100644 100644".parse::<Entry>()).is_err();
+    }
+
+    #[test]
+    fn error_when_invalid_xy() {
+        let entry = "1 WD N... 100644 100644 100644 1f2e 1f2e path".parse::<Entry>();
+        assert_that(entry).is_err();
+    }
+
+    fn file_state() -> impl Strategy<Value = FileState> {
+        prop_oneof![
+            Just(FileState::Unmodified),
+            Just(FileState::Modified),
+            Just(FileState::TypeChanged),
+            Just(FileState::Added),
+            Just(FileState::Deleted),
+            Just(FileState::Renamed),
+            Just(FileState::Copied),
+            Just(FileState::UpdatedButUnmerged),
+        ]
+    }
+
+    fn xy() -> impl Strategy<Value = Xy> {
+        (file_state(), file_state()).prop_map(|(index, worktree)| Xy { index, worktree })
+    }
+
+    fn submodule() -> impl Strategy<Value = Option<SubmoduleState>> {
+        proptest::option::of(any::<(bool, bool, bool)>().prop_map(
+            |(commit_changed, tracked_changes, untracked_changes)| SubmoduleState {
+                commit_changed,
+                tracked_changes,
+                untracked_changes,
+            },
+        ))
+    }
+
+    fn mode() -> impl Strategy<Value = u32> {
+        prop_oneof![
+            Just(0),
+            Just(0o100644),
+            Just(0o100755),
+            Just(0o120000),
+            Just(0o160000)
+        ]
+    }
+
+    fn oid() -> impl Strategy<Value = String> {
+        "[0-9a-f]{40}"
+    }
+
+    fn path() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9_./ \t\n\r\x01\"\\\\é-]{1,20}"
+    }
+
+    fn ref_name() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9_./-]{1,30}"
+    }
+
+    fn changed() -> impl Strategy<Value = Changed> {
+        (
+            xy(),
+            submodule(),
+            mode(),
+            mode(),
+            mode(),
+            oid(),
+            oid(),
+            path(),
+        )
+            .prop_map(
+                |(
+                    xy,
+                    submodule,
+                    head_mode,
+                    index_mode,
+                    worktree_mode,
+                    head_oid,
+                    index_oid,
+                    path,
+                )| {
+                    Changed {
+                        xy,
+                        submodule,
+                        head_mode,
+                        index_mode,
+                        worktree_mode,
+                        head_oid,
+                        index_oid,
+                        path,
+                    }
+                },
+            )
+    }
+
+    fn entry() -> impl Strategy<Value = Entry> {
+        let rename = (any::<bool>(), 0..=100u8, path()).prop_map(|(copied, score, path)| Rename {
+            kind: if copied {
+                RenameKind::Copied
+            } else {
+                RenameKind::Renamed
+            },
+            score,
+            original_path: path,
+        });
+        let unmerged = (
+            xy(),
+            submodule(),
+            [mode(), mode(), mode()],
+            mode(),
+            [oid(), oid(), oid()],
+            path(),
+        )
+            .prop_map(
+                |(xy, submodule, stage_modes, worktree_mode, stage_oids, path)| Unmerged {
+                    xy,
+                    submodule,
+                    stage_modes,
+                    worktree_mode,
+                    stage_oids,
+                    path,
+                },
+            );
+
+        prop_oneof![
+            changed().prop_map(Entry::Ordinary),
+            (changed(), rename)
+                .prop_map(|(changed, rename)| Entry::RenamedOrCopied(changed, rename)),
+            unmerged.prop_map(Entry::Unmerged),
+            path().prop_map(Entry::Untracked),
+            path().prop_map(Entry::Ignored),
+        ]
+    }
+
+    fn status() -> impl Strategy<Value = Status> {
+        let oid = proptest::option::of(prop_oneof![
+            Just(Oid::Initial),
+            super::tests::oid().prop_map(Oid::Commit),
+        ]);
+        let head = proptest::option::of(prop_oneof![
+            Just(Head::Detached),
+            ref_name().prop_map(Head::Branch),
+        ]);
+        let ahead_behind = proptest::option::of(
+            any::<(u64, u64)>().prop_map(|(ahead, behind)| AheadBehind { ahead, behind }),
+        );
+        (
+            oid,
+            head,
+            proptest::option::of(ref_name()),
+            ahead_behind,
+            proptest::collection::vec(entry(), 0..10),
+        )
+            .prop_map(|(oid, head, upstream, ahead_behind, entries)| Status {
+                oid,
+                head,
+                upstream,
+                ahead_behind,
+                entries,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn parses_what_it_displays(status in status()) {
+            let parsed = status.to_string().parse::<Status>();
+            prop_assert!(parsed.is_ok(), "{parsed:?}");
+            prop_assert_eq!(parsed.unwrap(), status);
+        }
+
+        #[test]
+        fn never_panics_on_arbitrary_output(output in "\\PC*(\n\\PC*){0,5}") {
+            let _ = output.parse::<Status>();
+        }
+
+        #[test]
+        fn never_panics_on_mangled_entries(status in status(), cut in 0..200usize) {
+            let output = status.to_string();
+            let cut = output.floor_char_boundary(cut.min(output.len()));
+            let _ = output[..cut].parse::<Status>();
+        }
+    }
+}
@@ -1,10 +1,17 @@
 use std::{
     env,
-    fmt::{self, Write},
     path::{Path, PathBuf},
 };
 
-use crate::{config, git_info::GitInfo, git_patch::GitPatch};
+use crate::{
+    config,
+    git_dir::GitDir,
+    git_info::GitInfo,
+    git_patch::GitPatch,
+    hg_info::HgInfo,
+    jj_info::JjInfo,
+    vcs::{VcsState, VcsStatus},
+};
 
 pub fn prompt(current_dir: &Path) -> anyhow::Result<String> {
     let current_dir_segment = format!(
@@ -14,13 +21,36 @@ pub fn prompt(current_dir: &Path) -> anyhow::Result<String> {
         config::CURRENT_DIR_SUFFIX
     );
 
-    let git_segment = if let Some(git_info) = GitInfo::from_git_status_output(current_dir)? {
-        let git_patch = GitPatch::detect(current_dir);
-        let content = git_segment_content(git_info, git_patch)?;
+    // jj is only asked when it's closer than git, to avoid spawning it for
+    // every plain git repository.
+    let jj_info = if config::JJ.is_some() && JjInfo::is_nearest_vcs(current_dir) {
+        JjInfo::from_jj_log_output(current_dir)?
+    } else {
+        None
+    };
+
+    let hg_dir = if config::HG.is_some() {
+        HgInfo::find_hg_dir(current_dir)
+    } else {
+        None
+    };
+
+    let vcs_status = if let Some(jj_info) = jj_info {
+        Some(jj_info.into_vcs_status())
+    } else if let Some(hg_dir) = hg_dir {
+        Some(HgInfo::from_hg_dir(current_dir, &hg_dir)?.into_vcs_status())
+    } else {
+        let git_dir = GitDir::find(current_dir);
+        GitInfo::from_git_status_output(current_dir, git_dir.as_ref())?.map(|git_info| {
+            let git_patches = git_dir.as_ref().map(GitPatch::detect).unwrap_or_default();
+            git_info.into_vcs_status(&git_patches)
+        })
+    };
+    let vcs_segment = if let Some(vcs_status) = vcs_status {
         format!(
             "{}{}{}",
-            config::GIT_PREFIX,
-            &shell_escape(&content),
+            vcs_prefix(&vcs_status),
+            &shell_escape(&vcs_status.to_string()),
             config::GIT_SUFFIX
         )
     } else {
@@ -28,13 +58,26 @@ pub fn prompt(current_dir: &Path) -> anyhow::Result<String> {
     };
 
     Ok(format!(
-        "{current_dir_segment}{git_segment}{}\n{}{} ",
+        "{current_dir_segment}{vcs_segment}{}\n{}{} ",
         config::RESET_STYLE,
         config::NEW_LINE,
         config::RESET_STYLE
     ))
 }
 
+// Protected branches take precedence, so that they stand out even when the
+// last commit is old.
+fn vcs_prefix(vcs_status: &VcsStatus) -> &'static str {
+    match vcs_status.state {
+        _ if vcs_status.protected => config::GIT_PROTECTED_PREFIX,
+        _ if vcs_status.stale => config::GIT_STALE_PREFIX,
+        Some(VcsState::Clean) => config::GIT_CLEAN_PREFIX,
+        Some(VcsState::Dirty) => config::GIT_DIRTY_PREFIX,
+        Some(VcsState::Conflict) => config::GIT_CONFLICT_PREFIX,
+        None => config::GIT_PREFIX,
+    }
+}
+
 fn current_dir_segment_content(current_dir: &Path) -> String {
     let current_dir = current_dir.home_to_tilde();
     match current_dir.components().count() {
@@ -50,42 +93,6 @@ fn current_dir_segment_content(current_dir: &Path) -> String {
     }
 }
 
-fn git_segment_content(
-    git_info: GitInfo,
-    git_patch: Option<GitPatch>,
-) -> Result<String, fmt::Error> {
-    if git_info.has_only_branch() && git_patch.is_none() {
-        return Ok(git_info.branch_name);
-    }
-
-    let mut s = git_info.branch_name;
-    s.push(' ');
-    if let Some(patch) = git_patch {
-        write!(s, "{patch}")?;
-    }
-    s.push_opt_str_if(config::GIT_UNSTAGED, git_info.unstaged);
-    s.push_opt_str_if(config::GIT_STAGED, git_info.staged);
-    s.push_opt_str_if(config::GIT_STASH, git_info.stash);
-    s.push_opt_str_if(config::GIT_UNTRACKED, git_info.untracked);
-    // Can't use .and because it's not const
-    if config::GIT_REMOTE.is_some()
-        && let Some(remote_diff) = git_info.remote_diff.as_ref()
-    {
-        write!(s, "{remote_diff}")?;
-    }
-    Ok(s)
-}
-
-impl GitInfo {
-    fn has_only_branch(&self) -> bool {
-        !(self.remote_diff.is_some()
-            || self.stash
-            || self.untracked
-            || self.unstaged
-            || self.staged)
-    }
-}
-
 fn shell_escape(s: &str) -> String {
     match config::SHELL {
         "zsh" => s.replace("%", "%%"),
@@ -113,96 +120,34 @@ impl PathExtensions for Path {
     }
 }
 
-trait StringBuilder {
-    fn push_opt_str_if(&mut self, opt_str: Option<&str>, cond: bool);
-}
-
-impl StringBuilder for String {
-    #[inline]
-    fn push_opt_str_if(&mut self, opt_str: Option<&str>, cond: bool) {
-        if cond && let Some(s) = opt_str {
-            self.push_str(s)
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use asserting::prelude::*;
 
-    mod git_segment_content {
+    mod vcs_prefix {
         use super::super::*;
         use super::*;
-        use crate::git_info::GitRemoteDiff;
-
-        #[test]
-        fn displays_git_segment_content_in_order() {
-            let branch = "feature/theropods";
-            let git_info = GitInfo {
-                branch_name: branch.to_string(),
-                remote_diff: Some(GitRemoteDiff {
-                    incoming: false,
-                    outgoing: false,
-                }),
-                stash: true,
-                untracked: true,
-                staged: true,
-                unstaged: true,
-            };
-            let git_patch = Some(GitPatch::Rebase);
-            let git_segment_content = git_segment_content(git_info, git_patch);
-            assert_that(git_segment_content).has_value(format!("{branch} B*+$%="));
-        }
 
         #[test]
-        fn does_not_include_trailing_space_if_only_branch_name() {
-            let branch = "feature/mobula";
-            let git_info = GitInfo {
-                branch_name: branch.to_string(),
-                remote_diff: None,
-                stash: false,
-                untracked: false,
-                staged: false,
-                unstaged: false,
-            };
-            let git_patch = None;
-            let git_segment_content = git_segment_content(git_info, git_patch);
-            assert_that(git_segment_content).has_value(branch);
+        fn uses_git_stale_prefix_when_stale() {
+            let mut vcs_status = VcsStatus::new("main".to_string());
+            vcs_status.state = Some(VcsState::Dirty);
+            vcs_status.stale = true;
+            assert_that(vcs_prefix(&vcs_status)).is_equal_to("%F{0}%K{196} ");
         }
 
         #[test]
-        fn skips_remote_symbols_if_no_remote() {
-            let branch = "feature/mellivora";
-            let git_info = GitInfo {
-                branch_name: branch.to_string(),
-                remote_diff: None,
-                stash: true,
-                untracked: false,
-                staged: true,
-                unstaged: false,
-            };
-            let git_patch = Some(GitPatch::CherryPick);
-            let git_segment_content = git_segment_content(git_info, git_patch);
-            assert_that(git_segment_content).has_value(format!("{branch} H+$"));
+        fn uses_git_protected_prefix_over_git_stale_prefix() {
+            let mut vcs_status = VcsStatus::new("main".to_string());
+            vcs_status.stale = true;
+            vcs_status.protected = true;
+            assert_that(vcs_prefix(&vcs_status)).is_equal_to("%F{15}%K{160} ");
         }
 
         #[test]
-        fn skips_patch_symbol_if_no_patch() {
-            let git_patch = None;
-            let branch = "feature/ateles";
-            let git_info = GitInfo {
-                branch_name: branch.to_string(),
-                remote_diff: Some(GitRemoteDiff {
-                    incoming: false,
-                    outgoing: true,
-                }),
-                stash: false,
-                untracked: false,
-                staged: true,
-                unstaged: false,
-            };
-            let git_segment_content = git_segment_content(git_info, git_patch);
-            assert_that(git_segment_content).has_value(format!("{branch} +>"));
+        fn uses_git_prefix_without_state() {
+            let vcs_status = VcsStatus::new("main".to_string());
+            assert_that(vcs_prefix(&vcs_status)).is_equal_to("%F{0}%K{220} ");
         }
     }
 
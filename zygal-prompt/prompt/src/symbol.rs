@@ -0,0 +1,85 @@
+use std::{borrow::Cow, fmt::Display};
+
+const COUNT_PLACEHOLDER: &str = "{count}";
+const NAME_PLACEHOLDER: &str = "{name}";
+const AGE_PLACEHOLDER: &str = "{age}";
+
+pub trait Symbol {
+    fn with_count(&self, count: impl Display) -> Cow<'_, str>;
+
+    fn with_name(&self, name: &str) -> Cow<'_, str>;
+
+    fn with_age(&self, age: &str) -> Cow<'_, str>;
+
+    fn with_placeholders(&self, values: &[(&str, Option<String>)]) -> Cow<'_, str>;
+}
+
+impl Symbol for str {
+    #[inline]
+    fn with_count(&self, count: impl Display) -> Cow<'_, str> {
+        self.with_placeholders(&[(COUNT_PLACEHOLDER, Some(count.to_string()))])
+    }
+
+    #[inline]
+    fn with_name(&self, name: &str) -> Cow<'_, str> {
+        self.with_placeholders(&[(NAME_PLACEHOLDER, Some(name.to_string()))])
+    }
+
+    #[inline]
+    fn with_age(&self, age: &str) -> Cow<'_, str> {
+        self.with_placeholders(&[(AGE_PLACEHOLDER, Some(age.to_string()))])
+    }
+
+    fn with_placeholders(&self, values: &[(&str, Option<String>)]) -> Cow<'_, str> {
+        // Only the text before the first placeholder without a value is kept
+        let end = values
+            .iter()
+            .filter(|(_, value)| value.is_none())
+            .filter_map(|(placeholder, _)| self.find(placeholder))
+            .min()
+            .unwrap_or(self.len());
+
+        let mut symbol = Cow::Borrowed(&self[..end]);
+        for (placeholder, value) in values {
+            if let Some(value) = value
+                && symbol.contains(placeholder)
+            {
+                symbol = Cow::Owned(symbol.replace(placeholder, value));
+            }
+        }
+        symbol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use asserting::prelude::*;
+
+    #[test]
+    fn replaces_count_placeholder() {
+        assert_that(">{count}".with_count(3)).is_equal_to(">3");
+    }
+
+    #[test]
+    fn keeps_symbol_without_count_placeholder() {
+        assert_that(">".with_count(3)).is_equal_to(">");
+    }
+
+    #[test]
+    fn replaces_all_placeholders() {
+        let values = [
+            ("{step}", Some("3".to_string())),
+            ("{total}", Some("7".to_string())),
+        ];
+        assert_that("B{step}/{total}".with_placeholders(&values)).is_equal_to("B3/7");
+    }
+
+    #[test]
+    fn truncates_at_first_placeholder_without_value() {
+        let values = [("{step}", Some("3".to_string())), ("{total}", None)];
+        assert_that("B{step}/{total}".with_placeholders(&values)).is_equal_to("B3/");
+        assert_that("B{total}-{step}".with_placeholders(&values)).is_equal_to("B");
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt::{Display, Write};
+
+use crate::symbol::Symbol;
+
+// What every backend reports, so that they all share the same rendering. The
+// backends choose the symbols from their own configuration.
+#[derive(Debug, PartialEq)]
+pub struct VcsStatus {
+    pub head: String,
+    pub bookmarks: Option<VcsBookmarks>,
+    pub upstream: Option<String>,
+    pub states: Vec<String>,
+    pub changes: Vec<VcsChange>,
+    pub divergence: Option<VcsDivergence>,
+    pub commit_age: Option<String>,
+    // The segment is shown in the stale or protected colors, when they are
+    // configured, the protected ones taking precedence.
+    pub stale: bool,
+    pub protected: bool,
+    // Unknown when the backend didn't look at the working tree
+    pub state: Option<VcsState>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum VcsState {
+    Clean,
+    Dirty,
+    Conflict,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VcsChange {
+    pub symbol: Option<&'static str>,
+    pub count: usize,
+}
+
+// Names pointing to the head, such as jj bookmarks, shown right after it
+#[derive(Debug, PartialEq)]
+pub struct VcsBookmarks {
+    pub symbol: &'static str,
+    pub names: Vec<String>,
+}
+
+// How many commits the head is ahead or behind its upstream, which may be gone
+#[derive(Debug, PartialEq)]
+pub struct VcsDivergence {
+    pub ahead: u64,
+    pub behind: u64,
+    pub gone: bool,
+    pub symbols: VcsDivergenceSymbols,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VcsDivergenceSymbols {
+    pub ahead: &'static str,
+    pub behind: &'static str,
+    pub on_par: &'static str,
+    pub gone: Option<&'static str>,
+}
+
+impl VcsStatus {
+    pub fn new(head: String) -> Self {
+        Self {
+            head,
+            bookmarks: None,
+            upstream: None,
+            states: Vec::new(),
+            changes: Vec::new(),
+            divergence: None,
+            commit_age: None,
+            stale: false,
+            protected: false,
+            state: None,
+        }
+    }
+
+    pub fn push_state(&mut self, symbol: Option<&str>, active: bool) {
+        if active && let Some(symbol) = symbol {
+            self.states.push(symbol.to_string());
+        }
+    }
+
+    pub fn push_change(&mut self, symbol: Option<&'static str>, count: usize) {
+        self.changes.push(VcsChange { symbol, count });
+    }
+}
+
+impl Display for VcsStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut symbols = self.states.concat();
+        for change in &self.changes {
+            if change.count != 0
+                && let Some(symbol) = change.symbol
+            {
+                symbols.push_str(&symbol.with_count(change.count));
+            }
+        }
+        if let Some(divergence) = self.divergence.as_ref() {
+            write!(symbols, "{divergence}")?;
+        }
+
+        write!(f, "{}", self.head)?;
+        if let Some(VcsBookmarks { symbol, names }) = self.bookmarks.as_ref()
+            && !names.is_empty()
+        {
+            write!(f, "{}", symbol.with_name(&names.join(",")))?;
+        }
+        if let Some(upstream) = self.upstream.as_ref() {
+            write!(f, "...{upstream}")?;
+        }
+        if !symbols.is_empty() {
+            write!(f, " {symbols}")?;
+        }
+        if let Some(commit_age) = self.commit_age.as_ref() {
+            write!(f, " {commit_age}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for VcsDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let VcsDivergenceSymbols {
+            ahead,
+            behind,
+            on_par,
+            gone,
+        } = self.symbols;
+
+        if self.gone {
+            if let Some(gone) = gone {
+                write!(f, "{gone}")?;
+            }
+            return Ok(());
+        }
+
+        if self.ahead == 0 && self.behind == 0 {
+            return write!(f, "{on_par}");
+        }
+
+        if self.behind != 0 {
+            write!(f, "{}", behind.with_count(self.behind))?;
+        }
+        if self.ahead != 0 {
+            write!(f, "{}", ahead.with_count(self.ahead))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use asserting::prelude::*;
+
+    #[test]
+    fn displays_head_states_changes_and_remote_in_order() {
+        let mut vcs_status = VcsStatus::new("main".to_string());
+        vcs_status.upstream = Some("origin/main".to_string());
+        vcs_status.push_state(Some("M"), true);
+        vcs_status.push_state(Some("B"), false);
+        vcs_status.push_change(Some("*{count}"), 2);
+        vcs_status.push_change(Some("+"), 0);
+        vcs_status.push_change(None, 4);
+        vcs_status.divergence = Some(divergence(1, 0, false));
+        vcs_status.commit_age = Some("3d".to_string());
+
+        assert_that(vcs_status.to_string()).is_equal_to("main...origin/main M*2>1 3d");
+    }
+
+    #[test]
+    fn displays_commit_age_without_symbols() {
+        let mut vcs_status = VcsStatus::new("main".to_string());
+        vcs_status.commit_age = Some("2h".to_string());
+        assert_that(vcs_status.to_string()).is_equal_to("main 2h");
+    }
+
+    #[test]
+    fn displays_only_head_without_symbols() {
+        let vcs_status = VcsStatus::new("default".to_string());
+        assert_that(vcs_status.to_string()).is_equal_to("default");
+    }
+
+    #[test]
+    fn displays_bookmarks_after_head() {
+        let mut vcs_status = VcsStatus::new("kmtzqqwl".to_string());
+        vcs_status.bookmarks = Some(VcsBookmarks {
+            symbol: "[{name}]",
+            names: vec!["main".to_string(), "feature/sauria".to_string()],
+        });
+        vcs_status.push_state(Some("E"), true);
+        assert_that(vcs_status.to_string()).is_equal_to("kmtzqqwl[main,feature/sauria] E");
+    }
+
+    #[test]
+    fn displays_divergence_on_par() {
+        assert_that(divergence(0, 0, false).to_string()).is_equal_to("=");
+    }
+
+    #[test]
+    fn displays_divergence_ahead() {
+        assert_that(divergence(4, 0, false).to_string()).is_equal_to(">4");
+    }
+
+    #[test]
+    fn displays_divergence_behind() {
+        assert_that(divergence(0, 2, false).to_string()).is_equal_to("<");
+    }
+
+    #[test]
+    fn displays_divergence_behind_then_ahead() {
+        assert_that(divergence(1, 5, false).to_string()).is_equal_to("<>1");
+    }
+
+    #[test]
+    fn displays_divergence_gone() {
+        assert_that(divergence(0, 0, true).to_string()).is_equal_to("~");
+    }
+
+    fn divergence(ahead: u64, behind: u64, gone: bool) -> VcsDivergence {
+        VcsDivergence {
+            ahead,
+            behind,
+            gone,
+            symbols: VcsDivergenceSymbols {
+                ahead: ">{count}",
+                behind: "<",
+                on_par: "=",
+                gone: Some("~"),
+            },
+        }
+    }
+}
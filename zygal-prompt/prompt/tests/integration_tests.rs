@@ -28,6 +28,22 @@ fn includes_git_info_when_in_git_repository() {
     ));
 }
 
+#[test]
+fn includes_untracked_when_file_name_is_only_whitespace() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "bacteria";
+    git_init(branch, repo_root);
+    fs::write(repo_root.join(" "), "")
+        .expect("Failed to write to file in prompt integration tests");
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} %% %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
 #[test]
 fn includes_merging_when_merge_conflicts() {
     let tmp_dir = mktemp();
@@ -43,7 +59,7 @@ fn includes_merging_when_merge_conflicts() {
     spawn_git(&["merge", other_branch], &sub_dir, true);
 
     assert_that(prompt(&sub_dir)).has_value(format!(
-        "%F{{0}}%K{{208}} */{sub_dir_name} %F{{0}}%K{{220}} {main_branch} M*+ %f%k\n%F{{0}}%K{{208}} %# %f%k "
+        "%F{{0}}%K{{208}} */{sub_dir_name} %F{{0}}%K{{220}} {main_branch} MX %f%k\n%F{{0}}%K{{208}} %# %f%k "
     ));
 }
 
@@ -62,14 +78,83 @@ fn includes_rebasing_when_rebase_conflicts() {
         true,
     );
 
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {other_branch} BX %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_sha_when_rebasing_detached_head() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (main_branch, other_branch) = ("insecta", "coleoptera");
+    git_init(main_branch, repo_root);
+    create_conflicting_files(repo_root, main_branch, other_branch);
+    git(&["switch", "--detach", other_branch], repo_root);
+    spawn_git(&["rebase", main_branch], repo_root, true);
+
     let sha = git(&["rev-parse", "--short", "HEAD"], repo_root);
     assert_that(prompt(repo_root)).has_value(format!(
-        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} ({}...) B*+ %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} ({}...) BX %f%k\n%F{{0}}%K{{208}} %# %f%k ",
         tmp_dir.path().display(),
         sha.trim()
     ));
 }
 
+#[test]
+fn includes_rebasing_when_apply_backend_rebase_conflicts() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (main_branch, other_branch) = ("crustacea", "decapoda");
+    git_init(main_branch, repo_root);
+    create_conflicting_files(repo_root, main_branch, other_branch);
+    git(&["switch", other_branch], repo_root);
+    spawn_git(&["rebase", "--apply", main_branch], repo_root, true);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {other_branch} BX %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_am_when_am_conflicts() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (main_branch, other_branch) = ("echinodermata", "asteroidea");
+    git_init(main_branch, repo_root);
+    create_conflicting_files(repo_root, main_branch, other_branch);
+    let patch = git(&["format-patch", "--stdout", "-1", other_branch], repo_root);
+    let patch_path = repo_root.join("starfish.patch");
+    let err_msg = format!("Failed to write patch to file {patch_path:?}");
+    fs::write(&patch_path, patch).expect(&err_msg);
+    spawn_git(&["am", "starfish.patch"], repo_root, true);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {main_branch} A%% %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_bisecting_when_bisect_in_progress() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "cnidaria";
+    git_init(branch, repo_root);
+    git(&["bisect", "start"], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} I %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
 #[test]
 fn includes_cherry_pick_when_cherry_pick_conflicts() {
     let tmp_dir = mktemp();
@@ -81,7 +166,7 @@ fn includes_cherry_pick_when_cherry_pick_conflicts() {
     spawn_git(&["cherry-pick", other_branch], repo_root, true);
 
     assert_that(prompt(repo_root)).has_value(format!(
-        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {main_branch} H*+ %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {main_branch} HX %f%k\n%F{{0}}%K{{208}} %# %f%k ",
         tmp_dir.path().display()
     ));
 }
@@ -105,7 +190,312 @@ fn includes_revert_when_revert_conflicts() {
     spawn_git(&["revert", "HEAD^"], repo_root, true);
 
     assert_that(prompt(repo_root)).has_value(format!(
-        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} V*+ %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} VX %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_gone_when_upstream_is_deleted() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (main_branch, upstream_branch) = ("mammuthus", "elephas");
+    git_init(main_branch, repo_root);
+    git(&["branch", upstream_branch], repo_root);
+    git(&["branch", "--set-upstream-to", upstream_branch], repo_root);
+    git(&["branch", "--delete", upstream_branch], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {main_branch} ~ %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_sha_when_detached_on_tag() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (branch, tag) = ("hominidae", "v1.2.0");
+    git_init(branch, repo_root);
+    git(&["tag", tag], repo_root);
+    git(&["switch", "--detach", tag], repo_root);
+    let sha = git(&["rev-parse", "--short=7", "HEAD"], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} ({}...) %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display(),
+        sha.trim()
+    ));
+}
+
+#[test]
+fn includes_merging_when_merge_conflicts_in_linked_worktree() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path().join("repository");
+    let worktree = tmp_dir.path().join("worktree");
+    fs::create_dir(&repo_root).expect("Failed to create repository in prompt integration tests");
+
+    let (main_branch, other_branch, worktree_branch) = ("annelida", "clitellata", "hirudinea");
+    git_init(main_branch, &repo_root);
+    create_conflicting_files(&repo_root, main_branch, other_branch);
+    let worktree_path = worktree.display().to_string();
+    git(
+        &[
+            "worktree",
+            "add",
+            "-b",
+            worktree_branch,
+            &worktree_path,
+            main_branch,
+        ],
+        &repo_root,
+    );
+    spawn_git(&["merge", other_branch], &worktree, true);
+
+    assert_that(prompt(&worktree)).has_value(format!(
+        "%F{{0}}%K{{208}} */worktree %F{{0}}%K{{220}} {worktree_branch} MX %f%k\n%F{{0}}%K{{208}} %# %f%k "
+    ));
+    assert_that(prompt(&repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} */repository %F{{0}}%K{{220}} {main_branch} %f%k\n%F{{0}}%K{{208}} %# %f%k "
+    ));
+}
+
+#[test]
+fn includes_cherry_pick_when_cherry_pick_conflicts_in_linked_worktree() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path().join("repository");
+    let worktree = tmp_dir.path().join("worktree");
+    fs::create_dir(&repo_root).expect("Failed to create repository in prompt integration tests");
+
+    let (main_branch, other_branch, worktree_branch) = ("mollusca", "bivalvia", "gastropoda");
+    git_init(main_branch, &repo_root);
+    create_conflicting_files(&repo_root, main_branch, other_branch);
+    let worktree_path = worktree.display().to_string();
+    git(
+        &[
+            "worktree",
+            "add",
+            "-b",
+            worktree_branch,
+            &worktree_path,
+            main_branch,
+        ],
+        &repo_root,
+    );
+    spawn_git(&["cherry-pick", other_branch], &worktree, true);
+
+    assert_that(prompt(&worktree)).has_value(format!(
+        "%F{{0}}%K{{208}} */worktree %F{{0}}%K{{220}} {worktree_branch} HX %f%k\n%F{{0}}%K{{208}} %# %f%k "
+    ));
+}
+
+#[test]
+fn includes_all_in_progress_operations_in_order() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (main_branch, other_branch) = ("porifera", "demospongiae");
+    git_init(main_branch, repo_root);
+    create_conflicting_files(repo_root, main_branch, other_branch);
+    git(&["bisect", "start"], repo_root);
+    spawn_git(&["merge", other_branch], repo_root, true);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {main_branch} MIX %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_submodules_when_submodule_is_modified() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path().join("cnidaria");
+    let library = tmp_dir.path().join("hydrozoa");
+    fs::create_dir(&repo_root).expect("Failed to create repository in prompt integration tests");
+    fs::create_dir(&library).expect("Failed to create library in prompt integration tests");
+
+    let (main_branch, library_branch) = ("anthozoa", "hydra");
+    git_init(main_branch, &repo_root);
+    git_init(library_branch, &library);
+    let library_path = library.display().to_string();
+    git(
+        &[
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            &library_path,
+            "polyps",
+        ],
+        &repo_root,
+    );
+    git(&["commit", "--message", "Add polyps"], &repo_root);
+    let submodule = repo_root.join("polyps");
+    fs::write(submodule.join("tentacles.txt"), "nematocysts")
+        .expect("Failed to write to file in submodule in prompt integration tests");
+
+    assert_that(prompt(&repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} */cnidaria %F{{0}}%K{{220}} {main_branch} ^ %f%k\n%F{{0}}%K{{208}} %# %f%k "
+    ));
+    assert_that(prompt(&submodule)).has_value(format!(
+        "%F{{0}}%K{{208}} */polyps %F{{0}}%K{{220}} {library_branch} %%= %f%k\n%F{{0}}%K{{208}} %# %f%k "
+    ));
+}
+
+#[test]
+fn includes_bare_when_in_bare_repository() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "tardigrada";
+    git(&["init", "--bare", "--initial-branch", branch], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} BARE:{branch} %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_git_dir_when_inside_git_directory() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    git_init("nematoda", repo_root);
+    let git_dir = repo_root.join(".git");
+
+    assert_that(prompt(&git_dir)).has_value(
+        "%F{0}%K{208} */.git %F{0}%K{220} GIT_DIR! %f%k\n%F{0}%K{208} %# %f%k ".to_string(),
+    );
+}
+
+#[test]
+fn includes_corrupt_index_when_index_is_corrupt() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "rotifera";
+    git_init(branch, repo_root);
+    fs::write(repo_root.join(".git/index"), "DIRC")
+        .expect("Failed to corrupt index in prompt integration tests");
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} !I %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_sparse_and_partial_in_sparse_partial_clone() {
+    let tmp_dir = mktemp();
+    let origin = tmp_dir.path().join("origin");
+    let clone = tmp_dir.path().join("clone");
+    fs::create_dir(&origin).expect("Failed to create origin in prompt integration tests");
+
+    let branch = "echinodermata";
+    git_init(branch, &origin);
+    let origin_url = format!("file://{}", origin.display());
+    let clone_path = clone.display().to_string();
+    git(
+        &["clone", "--filter=blob:none", &origin_url, &clone_path],
+        tmp_dir.path(),
+    );
+    git(&["sparse-checkout", "set", "asteroidea"], &clone);
+
+    assert_that(prompt(&clone)).has_value(format!(
+        "%F{{0}}%K{{208}} */clone %F{{0}}%K{{220}} {branch} SP= %f%k\n%F{{0}}%K{{208}} %# %f%k "
+    ));
+}
+
+#[test]
+fn includes_initial_when_no_commit_yet() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "ediacara";
+    git_init_without_commit(branch, &[], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} # %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_initial_and_staged_when_first_commit_is_staged() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "dickinsonia";
+    git_init_without_commit(branch, &[], repo_root);
+    fs::write(repo_root.join("fronds.txt"), "quilted")
+        .expect("Failed to write to file in prompt integration tests");
+    git(&["add", "--all"], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} #+ %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn includes_sha_when_detached_head_in_sha256_repository() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    git_init_without_commit("charnia", &["--object-format", "sha256"], repo_root);
+    git(
+        &["commit", "--allow-empty", "--message", "Ediacaran biota"],
+        repo_root,
+    );
+    git(&["switch", "--detach"], repo_root);
+    let sha = git(&["rev-parse", "HEAD"], repo_root);
+    assert_that(sha.trim().len()).is_equal_to(64);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} ({}...) %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display(),
+        &sha[..7]
+    ));
+}
+
+#[test]
+fn includes_branch_stash_only_when_stash_made_on_current_branch() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let (main_branch, other_branch) = ("trilobita", "agnostida");
+    git_init(main_branch, repo_root);
+    let file_path = repo_root.join("exoskeleton.txt");
+    fs::write(&file_path, "calcite").expect("Failed to write to file in prompt integration tests");
+    git(&["add", "--all"], repo_root);
+    git(&["stash", "push", "--message", "lenses"], repo_root);
+    git(&["switch", "--create", other_branch], repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {other_branch} $ %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+
+    git(&["switch", main_branch], repo_root);
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {main_branch} $& %f%k\n%F{{0}}%K{{208}} %# %f%k ",
+        tmp_dir.path().display()
+    ));
+}
+
+#[test]
+fn uses_git_colors_on_main_without_protected_branches() {
+    let tmp_dir = mktemp();
+    let repo_root = tmp_dir.path();
+
+    let branch = "main";
+    git_init(branch, repo_root);
+
+    assert_that(prompt(repo_root)).has_value(format!(
+        "%F{{0}}%K{{208}} {} %F{{0}}%K{{220}} {branch} %f%k\n%F{{0}}%K{{208}} %# %f%k ",
         tmp_dir.path().display()
     ));
 }
@@ -138,21 +528,28 @@ fn spawn_git(args: &[&str], current_dir: &Path, expect_failure: bool) -> String
 }
 
 fn git_init(branch: &str, current_dir: &Path) {
-    git(&["init", "--initial-branch", branch], current_dir);
+    git_init_without_commit(branch, &[], current_dir);
     git(
-        &["config", "--local", "user.name", "Charles Darwin"],
+        &["commit", "--allow-empty", "--message", "Cambrian explosion"],
         current_dir,
     );
+}
+
+fn git_init_without_commit(branch: &str, init_args: &[&str], current_dir: &Path) {
     git(
-        &["config", "--local", "user.email", "charles.darwin@downe.uk"],
+        &[&["init", "--initial-branch", branch], init_args].concat(),
         current_dir,
     );
     git(
-        &["config", "--local", "commit.gpgsign", "false"],
+        &["config", "--local", "user.name", "Charles Darwin"],
         current_dir,
     );
     git(
-        &["commit", "--allow-empty", "--message", "Cambrian explosion"],
+        &["config", "--local", "user.email", "charles.darwin@downe.uk"],
+        current_dir,
+    );
+    git(
+        &["config", "--local", "commit.gpgsign", "false"],
         current_dir,
     );
 }